@@ -0,0 +1,253 @@
+use std::{path::Path, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::{System::Diagnostics::Debug::MessageBeep, UI::WindowsAndMessaging::MB_ICONEXCLAMATION};
+
+use crate::{
+    config::{load_json, save_json},
+    engine::EngineSnapshot,
+    serialization::hex_vec
+};
+
+pub const ALERT_RULES_FILE: &str = "alert-rules.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum AlertCondition {
+    // A slot that held an object is free.
+    SlotFreed { slot: u16 },
+    RespawnFailureChanged,
+    // An object whose tag path contains `tag_path` takes a slot, any slot when `slot` is None.
+    TagSpawned { slot: Option<u16>, tag_path: String },
+    // The object pool's next ID reaches `value`, IDs wrap around so passing it counts as well.
+    NextObjectIdReaches { value: u16 },
+    // The number of occupied slots goes from below `threshold` to at or above it, or back down.
+    ObjectCountCrosses { threshold: u16 }
+}
+
+pub const ALERT_CONDITIONS: [&str; 5] = ["Slot Freed", "Respawn Failure Changed", "Tag Spawned", "Next Object ID Reaches", "Object Count Crosses"];
+
+impl AlertCondition {
+    // Index into ALERT_CONDITIONS.
+    pub fn kind(&self) -> usize {
+        match self {
+            AlertCondition::SlotFreed { .. } => 0,
+            AlertCondition::RespawnFailureChanged => 1,
+            AlertCondition::TagSpawned { .. } => 2,
+            AlertCondition::NextObjectIdReaches { .. } => 3,
+            AlertCondition::ObjectCountCrosses { .. } => 4
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            AlertCondition::SlotFreed { slot } => format!("Slot {} is freed", slot),
+            AlertCondition::RespawnFailureChanged => "Respawn failure changes".to_string(),
+            AlertCondition::TagSpawned { slot: Some(slot), tag_path } => format!("\"{}\" spawns into slot {}", tag_path, slot),
+            AlertCondition::TagSpawned { slot: None, tag_path } => format!("\"{}\" spawns", tag_path),
+            AlertCondition::NextObjectIdReaches { value } => format!("Next object ID reaches {}", value),
+            AlertCondition::ObjectCountCrosses { threshold } => format!("Object count crosses {}", threshold)
+        }
+    }
+
+    // What happened between two snapshots, one message per match.
+    fn check(&self, previous: &EngineSnapshot, current: &EngineSnapshot) -> Vec<String> {
+        match self {
+            AlertCondition::SlotFreed { slot } => {
+                let index = *slot as usize;
+                let occupied = |snapshot: &EngineSnapshot| snapshot.object_pool_entries.get(index).is_some_and(|entry| entry.is_some());
+
+                if occupied(previous) && !occupied(current) {
+                    vec![format!("Slot {} was freed, it held {}", index, tag_path(previous, index))]
+                } else {
+                    Vec::new()
+                }
+            }
+            AlertCondition::RespawnFailureChanged => {
+                let (before, after) = (previous.player_globals.respawn_failure, current.player_globals.respawn_failure);
+                if before != after {
+                    vec![format!("Respawn failure changed from {} to {}", before, after)]
+                } else {
+                    Vec::new()
+                }
+            }
+            AlertCondition::TagSpawned { slot, tag_path: pattern } => {
+                let pattern = pattern.to_lowercase();
+                let slots = match slot {
+                    Some(slot) => *slot as usize..*slot as usize + 1,
+                    None => 0..current.object_pool_entries.len()
+                };
+
+                slots.filter(|index| {
+                        let id = |snapshot: &EngineSnapshot| snapshot.object_pool_entries.get(*index).and_then(|entry| entry.as_ref()).map(|entry| entry.id);
+                        id(current).is_some() && id(current) != id(previous)
+                    })
+                    .map(|index| (index, tag_path(current, index)))
+                    .filter(|(_, path)| path.to_lowercase().contains(&pattern))
+                    .map(|(index, path)| format!("{} spawned into slot {}", path, index))
+                    .collect()
+            }
+            AlertCondition::NextObjectIdReaches { value } => {
+                let (before, after) = (previous.object_pool_header.next_object_id, current.object_pool_header.next_object_id);
+                let reached = if before <= after {
+                    before < *value && *value <= after
+                } else {
+                    before < *value || *value <= after
+                };
+
+                if reached {
+                    vec![format!("Next object ID reached {}, now {}", value, after)]
+                } else {
+                    Vec::new()
+                }
+            }
+            AlertCondition::ObjectCountCrosses { threshold } => {
+                let count = |snapshot: &EngineSnapshot| snapshot.object_pool_entries.iter().filter(|entry| entry.is_some()).count();
+                let (before, after, threshold) = (count(previous), count(current), *threshold as usize);
+
+                if before < threshold && after >= threshold {
+                    vec![format!("Object count rose to {}", after)]
+                } else if before >= threshold && after < threshold {
+                    vec![format!("Object count fell to {}", after)]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+fn tag_path(snapshot: &EngineSnapshot, index: usize) -> String {
+    snapshot.game_object_entries.get(index)
+        .and_then(|entry| entry.as_ref())
+        .and_then(|game_object| snapshot.tags.get(&game_object.tag_index))
+        .cloned()
+        .unwrap_or("UNKNOWN".to_string())
+}
+
+// Bytes written to a raw physical guest address through the write journal.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlertWrite {
+    pub address: usize,
+    #[serde(with = "hex_vec")]
+    pub bytes: Vec<u8>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertActions {
+    pub toast: bool,
+    pub sound: bool,
+    // A line in the Alerts log and on the console.
+    pub log: bool,
+    pub write: Option<AlertWrite>
+}
+
+impl Default for AlertActions {
+    fn default() -> AlertActions {
+        AlertActions {
+            toast: true,
+            sound: false,
+            log: true,
+            write: None
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    #[serde(default)]
+    pub actions: AlertActions
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+pub fn load_alert_rules(path: &Path) -> Result<Vec<AlertRule>, String> {
+    load_json(path)
+}
+
+pub fn save_alert_rules(path: &Path, rules: &[AlertRule]) -> Result<(), String> {
+    save_json(path, rules)
+}
+
+#[derive(Debug)]
+pub struct FiredAlert {
+    pub rule: String,
+    pub message: String,
+    pub timestamp: Duration,
+    // Game tick of the snapshot the rule matched on.
+    pub tick: Option<u32>
+}
+
+pub struct Toast {
+    pub text: String,
+    pub shown_at: Instant
+}
+
+pub struct AlertLog {
+    pub alerts: Vec<FiredAlert>,
+    // Toasts not yet dismissed, the viewer drops them once they expire.
+    pub toasts: Vec<Toast>
+}
+
+impl AlertLog {
+    pub fn new() -> AlertLog {
+        AlertLog {
+            alerts: Vec::new(),
+            toasts: Vec::new()
+        }
+    }
+
+    // Run every enabled rule against two consecutive snapshots and carry out the toast, sound and log actions.
+    // Memory writes are returned for the caller to make, since they go through its journal.
+    pub fn process(&mut self, rules: &[AlertRule], previous: &EngineSnapshot, current: &EngineSnapshot, timestamp: Duration) -> Vec<(String, AlertWrite)> {
+        let mut writes = Vec::new();
+        let mut beep = false;
+
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            let messages = rule.condition.check(previous, current);
+            if messages.is_empty() {
+                continue;
+            }
+
+            for message in messages {
+                if rule.actions.toast {
+                    self.toasts.push(Toast {
+                        text: format!("{}: {}", rule.name, message),
+                        shown_at: Instant::now()
+                    });
+                }
+
+                if rule.actions.log {
+                    println!("Alert {}: {}", rule.name, message);
+                    self.alerts.push(FiredAlert {
+                        rule: rule.name.clone(),
+                        message: message,
+                        timestamp: timestamp,
+                        tick: current.game_tick
+                    });
+                }
+            }
+
+            beep |= rule.actions.sound;
+
+            if let Some(write) = &rule.actions.write {
+                writes.push((rule.name.clone(), write.clone()));
+            }
+        }
+
+        // One beep per snapshot however many rules asked for it.
+        if beep {
+            unsafe {
+                let _ = MessageBeep(MB_ICONEXCLAMATION);
+            }
+        }
+
+        writes
+    }
+}
@@ -0,0 +1,151 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    engine::{find_game_profile, GameProfile, GAME_PROFILES, HALO_XBOX_RETAIL},
+    sampler::DEFAULT_SAMPLE_RATE
+};
+
+pub const USAGE: &str = "Usage: objectviewer [options]
+
+Options:
+    --process <name>        Name of the emulator process (default: xemu.exe)
+    --pid <pid>             Process to attach to, defaults to the only running process named by --process
+    --address <hex>         Virtual address of guest RAM, the result of (gpa2hva 0x0), found on its own when left out
+    --dump <path>           Open a RAM dump, saved snapshot, recorded session or folder of them instead of a running process
+    --profile <name>        Game build to decode (default: halo-xbox-retail)
+    --read-only             Open xemu without write rights and disable all editing
+    --tick-sync             Take one snapshot per game tick instead of sampling at --sample-rate, not in headless mode
+    --pause-reads           Suspend the emulator while each snapshot is read so it is consistent
+    --sample-rate <hz>      How often guest memory is read in the viewer, independent of the display (default: 60)
+    --headless              Print snapshots to stdout instead of opening a window
+    --interval <ms>         Keep sampling at this interval instead of printing a single snapshot, with --dump the time between frames
+    --format <format>       json, ndjson, csv or snapshot (default: json)
+    --table <table>         Table printed as csv: objects, players or globals (default: objects)
+    --help                  Show this message
+
+Settings changed in the viewer are remembered and used as the defaults of the next run.";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Ndjson,
+    Csv,
+    // Full versioned snapshot document that can be loaded back into the viewer.
+    Snapshot
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsvTable {
+    Objects,
+    Players,
+    Globals
+}
+
+pub struct Options {
+    pub help: bool,
+    pub headless: bool,
+    pub process: String,
+    pub pid: Option<u32>,
+    pub address: Option<usize>,
+    pub dump: Option<PathBuf>,
+    pub profile: &'static GameProfile,
+    pub read_only: bool,
+    pub sample_rate: f32,
+    pub tick_sync: bool,
+    pub suspend_while_reading: bool,
+    pub interval: Option<Duration>,
+    pub format: OutputFormat,
+    pub table: CsvTable
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            help: false,
+            headless: false,
+            process: "xemu.exe".to_string(),
+            pid: None,
+            address: None,
+            dump: None,
+            profile: &HALO_XBOX_RETAIL,
+            read_only: false,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            tick_sync: false,
+            suspend_while_reading: false,
+            interval: None,
+            format: OutputFormat::Json,
+            table: CsvTable::Objects
+        }
+    }
+}
+
+// Options not given on the command line keep their value from `defaults`.
+pub fn parse_args(args: impl IntoIterator<Item = String>, defaults: Options) -> Result<Options, String> {
+    let mut options = defaults;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--help" | "-h" => options.help = true,
+            "--headless" => options.headless = true,
+            "--read-only" => options.read_only = true,
+            "--tick-sync" => options.tick_sync = true,
+            "--pause-reads" => options.suspend_while_reading = true,
+            "--process" => options.process = value()?,
+            "--pid" => {
+                let value = value()?;
+                options.pid = Some(value.parse().map_err(|_| format!("Invalid pid: {}", value))?);
+            }
+            "--address" => options.address = Some(parse_hex(&value()?)?),
+            "--dump" => options.dump = Some(PathBuf::from(value()?)),
+            "--profile" => {
+                let value = value()?;
+                options.profile = find_game_profile(&value).ok_or(format!(
+                    "Unknown profile: {}, expected one of: {}",
+                    value,
+                    GAME_PROFILES.iter().map(|profile| profile.name).collect::<Vec<_>>().join(", ")
+                ))?;
+            }
+            // --refresh-rate is the name used before sampling moved off the UI thread.
+            "--sample-rate" | "--refresh-rate" => {
+                let value = value()?;
+                let rate: f32 = value.parse().map_err(|_| format!("Invalid sample rate: {}", value))?;
+                if rate <= 0.0 {
+                    return Err(format!("Invalid sample rate: {}", value));
+                }
+                options.sample_rate = rate;
+            }
+            "--interval" => {
+                let value = value()?;
+                options.interval = Some(Duration::from_millis(value.parse().map_err(|_| format!("Invalid interval: {}", value))?));
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "json" => OutputFormat::Json,
+                    "ndjson" => OutputFormat::Ndjson,
+                    "csv" => OutputFormat::Csv,
+                    "snapshot" => OutputFormat::Snapshot,
+                    other => return Err(format!("Unknown format: {}", other))
+                };
+            }
+            "--table" => {
+                options.table = match value()?.as_str() {
+                    "objects" => CsvTable::Objects,
+                    "players" => CsvTable::Players,
+                    "globals" => CsvTable::Globals,
+                    other => return Err(format!("Unknown table: {}", other))
+                };
+            }
+            other => return Err(format!("Unknown argument: {}", other))
+        }
+    }
+
+    Ok(options)
+}
+
+pub fn parse_hex(value: &str) -> Result<usize, String> {
+    let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(digits, 16).map_err(|_| format!("Invalid hex address: {}", value))
+}
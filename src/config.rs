@@ -0,0 +1,146 @@
+use std::{fs, io::ErrorKind, path::{Path, PathBuf}};
+
+use imgui::Style;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    cli::Options,
+    engine::find_game_profile,
+    sampler::DEFAULT_SAMPLE_RATE
+};
+
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+    Classic
+}
+
+pub const THEMES: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::Classic];
+
+impl Theme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::Classic => "Classic"
+        }
+    }
+
+    pub fn apply(&self, style: &mut Style) {
+        match self {
+            Theme::Dark => style.use_dark_colors(),
+            Theme::Light => style.use_light_colors(),
+            Theme::Classic => style.use_classic_colors()
+        };
+    }
+}
+
+// Viewer settings remembered between runs. Command line options win over these for the run they are given on.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub process_name: String,
+    // Guest RAM moves every time xemu starts, so this only fills in the attach screen.
+    pub virtual_address: String,
+    pub profile: String,
+    pub read_only: bool,
+    pub sample_rate: f32,
+    pub tick_sync: bool,
+    pub suspend_while_reading: bool,
+    pub target_index: u32,
+    pub journal_path: String,
+    pub theme: Theme,
+    pub show_players: bool,
+    pub show_inspector: bool,
+    pub show_watchlist: bool,
+    pub show_alerts: bool,
+    pub show_logs: bool,
+    pub window_size: [u32; 2],
+    pub window_position: Option<[i32; 2]>
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let options = Options::default();
+
+        Config {
+            process_name: options.process,
+            virtual_address: String::default(),
+            profile: options.profile.name.to_string(),
+            read_only: options.read_only,
+            sample_rate: options.sample_rate,
+            tick_sync: options.tick_sync,
+            suspend_while_reading: options.suspend_while_reading,
+            target_index: 0,
+            journal_path: String::from("journal.json"),
+            theme: Theme::Dark,
+            show_players: true,
+            show_inspector: true,
+            show_watchlist: true,
+            show_alerts: true,
+            show_logs: true,
+            window_size: [1600, 900],
+            window_position: None
+        }
+    }
+}
+
+impl Config {
+    // Defaults for the command line, taken from the last run.
+    pub fn options(&self) -> Options {
+        let mut options = Options::default();
+        options.process = self.process_name.clone();
+        options.read_only = self.read_only;
+        options.sample_rate = if self.sample_rate > 0.0 { self.sample_rate } else { DEFAULT_SAMPLE_RATE };
+        options.tick_sync = self.tick_sync;
+        options.suspend_while_reading = self.suspend_while_reading;
+
+        if let Some(profile) = find_game_profile(&self.profile) {
+            options.profile = profile;
+        }
+
+        options
+    }
+}
+
+// Files the viewer keeps between runs live in the user's config folder, or the working directory when there is none.
+pub fn config_path(file_name: &str) -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join("objectviewer").join(file_name),
+        None => PathBuf::from(file_name)
+    }
+}
+
+// A file that does not exist yet loads as the default value, any other read error is returned.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(format!("Could not read {}: {}", path.display(), e))
+    };
+
+    serde_json::from_str(&json).map_err(|e| format!("Could not parse {}: {}", path.display(), e))
+}
+
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+pub fn load_config() -> Config {
+    load_json(&config_path(CONFIG_FILE)).unwrap_or_else(|e| {
+        println!("{}, using the default settings.", e);
+        Config::default()
+    })
+}
+
+pub fn save_config(config: &Config) -> Result<(), String> {
+    save_json(&config_path(CONFIG_FILE), config)
+}
@@ -0,0 +1,317 @@
+use crate::engine::{DatumHandle, EngineSnapshot};
+
+// Distance in world units an object has to travel between two snapshots to be reported as moved.
+pub const DEFAULT_MOVE_THRESHOLD: f32 = 1.0;
+
+#[derive(Debug, Clone)]
+pub enum SnapshotEvent {
+    ObjectCreated {
+        datum: DatumHandle,
+        tag_index: u32
+    },
+    ObjectDeleted {
+        datum: DatumHandle,
+        tag_index: u32
+    },
+    // The slot was freed and reused between the two snapshots.
+    SaltChanged {
+        previous: DatumHandle,
+        previous_tag_index: u32,
+        current: DatumHandle,
+        tag_index: u32
+    },
+    ObjectMoved {
+        datum: DatumHandle,
+        from: [f32; 3],
+        to: [f32; 3]
+    },
+    // None while the player slot is empty, so joining and leaving are reported too.
+    PlayerUnitChanged {
+        player_index: usize,
+        previous: Option<DatumHandle>,
+        current: Option<DatumHandle>
+    },
+    DeadPlayerDatumChanged {
+        local_player_index: usize,
+        previous: DatumHandle,
+        current: DatumHandle
+    }
+}
+
+impl SnapshotEvent {
+    // Datum that no longer exists in the current snapshot, if the event removed one.
+    pub fn freed_datum(&self) -> Option<(&DatumHandle, u32)> {
+        match self {
+            SnapshotEvent::ObjectDeleted { datum, tag_index } => Some((datum, *tag_index)),
+            SnapshotEvent::SaltChanged { previous, previous_tag_index, .. } => Some((previous, *previous_tag_index)),
+            _ => None
+        }
+    }
+}
+
+pub fn distance(from: &[f32; 3], to: &[f32; 3]) -> f32 {
+    let x = to[0] - from[0];
+    let y = to[1] - from[1];
+    let z = to[2] - from[2];
+    (x * x + y * y + z * z).sqrt()
+}
+
+pub fn diff_snapshots(previous: &EngineSnapshot, current: &EngineSnapshot, move_threshold: f32) -> Vec<SnapshotEvent> {
+    let mut events = Vec::new();
+
+    let slot_count = previous.object_pool_entries.len().max(current.object_pool_entries.len());
+    for index in 0..slot_count {
+        let before = object_at(previous, index);
+        let after = object_at(current, index);
+
+        match (before, after) {
+            (None, Some((datum, tag_index, _))) => {
+                events.push(SnapshotEvent::ObjectCreated { datum: datum, tag_index: tag_index });
+            }
+            (Some((datum, tag_index, _)), None) => {
+                events.push(SnapshotEvent::ObjectDeleted { datum: datum, tag_index: tag_index });
+            }
+            (Some((previous_datum, previous_tag_index, from)), Some((datum, tag_index, to))) => {
+                if previous_datum != datum {
+                    events.push(SnapshotEvent::SaltChanged {
+                        previous: previous_datum,
+                        previous_tag_index: previous_tag_index,
+                        current: datum,
+                        tag_index: tag_index
+                    });
+                } else if distance(&from, &to) > move_threshold {
+                    events.push(SnapshotEvent::ObjectMoved { datum: datum, from: from, to: to });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    let player_count = previous.player_pool_entries.len().max(current.player_pool_entries.len());
+    for player_index in 0..player_count {
+        let before = player_unit(previous, player_index);
+        let after = player_unit(current, player_index);
+        if before != after {
+            events.push(SnapshotEvent::PlayerUnitChanged {
+                player_index: player_index,
+                previous: before,
+                current: after
+            });
+        }
+    }
+
+    let dead_players = previous.player_globals.local_dead_players.iter().zip(current.player_globals.local_dead_players.iter());
+    for (local_player_index, (before, after)) in dead_players.enumerate() {
+        if before != after {
+            events.push(SnapshotEvent::DeadPlayerDatumChanged {
+                local_player_index: local_player_index,
+                previous: before.clone(),
+                current: after.clone()
+            });
+        }
+    }
+
+    events
+}
+
+fn player_unit(snapshot: &EngineSnapshot, index: usize) -> Option<DatumHandle> {
+    let player = snapshot.player_pool_entries.get(index)?.as_ref()?;
+    Some(player.slave_unit_index.clone())
+}
+
+fn object_at(snapshot: &EngineSnapshot, index: usize) -> Option<(DatumHandle, u32, [f32; 3])> {
+    let pool_entry = snapshot.object_pool_entries.get(index)?.as_ref()?;
+    let game_object = snapshot.game_object_entries.get(index)?.as_ref()?;
+
+    Some((DatumHandle::new_from_index_id(index as u16, pool_entry.id), game_object.tag_index, game_object.position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{struct_from_bytes, GameObject, ObjectPoolEntry, PlayerPoolEntry};
+
+    // Zeroed bytes every engine struct can be built from.
+    const ZEROES: [u8; 0x400] = [0; 0x400];
+
+    fn empty_snapshot() -> EngineSnapshot {
+        EngineSnapshot {
+            object_pool_header: struct_from_bytes(&ZEROES),
+            object_pool_entries: (0..8).map(|_| None).collect(),
+            game_object_entries: (0..8).map(|_| None).collect(),
+            player_pool_header: struct_from_bytes(&ZEROES),
+            player_globals: struct_from_bytes(&ZEROES),
+            player_pool_entries: (0..2).map(|_| None).collect(),
+            tags: Default::default(),
+            tag_entries: Default::default(),
+            game_tick: None
+        }
+    }
+
+    fn place_object(snapshot: &mut EngineSnapshot, index: usize, id: u16, tag_index: u32, position: [f32; 3]) {
+        let mut pool_entry: ObjectPoolEntry = struct_from_bytes(&ZEROES);
+        pool_entry.id = id;
+        let mut game_object: GameObject = struct_from_bytes(&ZEROES);
+        game_object.tag_index = tag_index;
+        game_object.position = position;
+
+        snapshot.object_pool_entries[index] = Some(pool_entry);
+        snapshot.game_object_entries[index] = Some(game_object);
+    }
+
+    fn place_player(snapshot: &mut EngineSnapshot, index: usize, unit: DatumHandle) {
+        let mut player: PlayerPoolEntry = struct_from_bytes(&ZEROES);
+        player.slave_unit_index = unit;
+        snapshot.player_pool_entries[index] = Some(player);
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_events() {
+        let mut snapshot = empty_snapshot();
+        place_object(&mut snapshot, 1, 0xE001, 7, [1.0, 2.0, 3.0]);
+        place_player(&mut snapshot, 0, DatumHandle::new_from_index_id(1, 0xE001));
+
+        let mut same = empty_snapshot();
+        place_object(&mut same, 1, 0xE001, 7, [1.0, 2.0, 3.0]);
+        place_player(&mut same, 0, DatumHandle::new_from_index_id(1, 0xE001));
+
+        assert!(diff_snapshots(&snapshot, &same, DEFAULT_MOVE_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn object_created() {
+        let previous = empty_snapshot();
+        let mut current = empty_snapshot();
+        place_object(&mut current, 3, 0xE003, 42, [0.0; 3]);
+
+        match diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::ObjectCreated { datum, tag_index }] => {
+                assert_eq!(*datum, DatumHandle::new_from_index_id(3, 0xE003));
+                assert_eq!(*tag_index, 42);
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+
+    #[test]
+    fn object_deleted() {
+        let mut previous = empty_snapshot();
+        place_object(&mut previous, 3, 0xE003, 42, [0.0; 3]);
+        let current = empty_snapshot();
+
+        let events = diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD);
+        match events.as_slice() {
+            [SnapshotEvent::ObjectDeleted { datum, tag_index }] => {
+                assert_eq!(*datum, DatumHandle::new_from_index_id(3, 0xE003));
+                assert_eq!(*tag_index, 42);
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+        assert_eq!(events[0].freed_datum().map(|(datum, tag_index)| (datum.get_handle(), tag_index)), Some((DatumHandle::new_from_index_id(3, 0xE003).get_handle(), 42)));
+    }
+
+    #[test]
+    fn salt_changed() {
+        let mut previous = empty_snapshot();
+        place_object(&mut previous, 5, 0xE005, 10, [0.0; 3]);
+        let mut current = empty_snapshot();
+        place_object(&mut current, 5, 0xE006, 11, [50.0; 3]);
+
+        // A reused slot is reported once, not also as a move.
+        match diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::SaltChanged { previous, previous_tag_index, current, tag_index }] => {
+                assert_eq!(*previous, DatumHandle::new_from_index_id(5, 0xE005));
+                assert_eq!(*previous_tag_index, 10);
+                assert_eq!(*current, DatumHandle::new_from_index_id(5, 0xE006));
+                assert_eq!(*tag_index, 11);
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+
+    #[test]
+    fn object_moved_past_threshold() {
+        let mut previous = empty_snapshot();
+        place_object(&mut previous, 2, 0xE002, 1, [0.0, 0.0, 0.0]);
+        let mut current = empty_snapshot();
+        place_object(&mut current, 2, 0xE002, 1, [3.0, 4.0, 0.0]);
+
+        match diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::ObjectMoved { datum, from, to }] => {
+                assert_eq!(*datum, DatumHandle::new_from_index_id(2, 0xE002));
+                assert_eq!(*from, [0.0, 0.0, 0.0]);
+                assert_eq!(*to, [3.0, 4.0, 0.0]);
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+
+    #[test]
+    fn object_moved_below_threshold() {
+        let mut previous = empty_snapshot();
+        place_object(&mut previous, 2, 0xE002, 1, [0.0, 0.0, 0.0]);
+        let mut current = empty_snapshot();
+        place_object(&mut current, 2, 0xE002, 1, [0.5, 0.5, 0.0]);
+
+        assert!(diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).is_empty());
+        assert_eq!(diff_snapshots(&previous, &current, 0.5).len(), 1);
+    }
+
+    #[test]
+    fn player_unit_changed() {
+        let mut previous = empty_snapshot();
+        place_player(&mut previous, 1, DatumHandle::new_from_index_id(4, 0xE004));
+        let mut current = empty_snapshot();
+        place_player(&mut current, 1, DatumHandle::new_from_index_id(6, 0xE006));
+
+        match diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::PlayerUnitChanged { player_index, previous, current }] => {
+                assert_eq!(*player_index, 1);
+                assert_eq!(*previous, Some(DatumHandle::new_from_index_id(4, 0xE004)));
+                assert_eq!(*current, Some(DatumHandle::new_from_index_id(6, 0xE006)));
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+
+    #[test]
+    fn player_joined_and_left() {
+        let empty = empty_snapshot();
+        let mut joined = empty_snapshot();
+        place_player(&mut joined, 0, DatumHandle::new_from_index_id(3, 0xE003));
+
+        match diff_snapshots(&empty, &joined, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::PlayerUnitChanged { player_index, previous, current }] => {
+                assert_eq!(*player_index, 0);
+                assert_eq!(*previous, None);
+                assert_eq!(*current, Some(DatumHandle::new_from_index_id(3, 0xE003)));
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+
+        match diff_snapshots(&joined, &empty, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::PlayerUnitChanged { player_index, previous, current }] => {
+                assert_eq!(*player_index, 0);
+                assert_eq!(*previous, Some(DatumHandle::new_from_index_id(3, 0xE003)));
+                assert_eq!(*current, None);
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+
+    #[test]
+    fn dead_player_datum_changed() {
+        let previous = empty_snapshot();
+        let mut current = empty_snapshot();
+        current.player_globals.local_dead_players[2] = DatumHandle::new_from_index_id(9, 0xE009);
+
+        match diff_snapshots(&previous, &current, DEFAULT_MOVE_THRESHOLD).as_slice() {
+            [SnapshotEvent::DeadPlayerDatumChanged { local_player_index, previous, current }] => {
+                assert_eq!(*local_player_index, 2);
+                assert_eq!(*previous, DatumHandle::new_from_index_id(0, 0));
+                assert_eq!(*current, DatumHandle::new_from_index_id(9, 0xE009));
+            }
+            events => panic!("Unexpected events {:?}", events)
+        }
+    }
+}
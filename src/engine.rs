@@ -1,4 +1,11 @@
-use std::{collections::HashMap, ffi::CStr, fmt::{self}};
+use std::{collections::HashMap, fmt::{self}, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    memory::GuestMemory,
+    serialization::{fourcc, hex_bytes}
+};
 
 // Halo 1 Xbox Retail
 const HALO_OBJECT_POOL_HEADER_ADDR: usize = 0x000B9370;
@@ -10,10 +17,45 @@ const HALO_TAG_HEADER_ADDR: usize = 0x003A6000;
 const HALO_PLAYER_GLOBALS_ADDR: usize = 0x00214E00;
 const MAXIMUM_NUMBER_OF_LOCAL_PLAYERS: usize = 4;
 
+// The simulation runs at a fixed rate regardless of frame rate.
+pub const GAME_TICKS_PER_SECOND: u32 = 30;
+
+// Engine globals all live below this, it bounds the search for the game time globals.
+pub const GAME_GLOBALS_SEARCH_END: usize = 0x00400000;
+
 // Halo 1 Xbox Max Objects
 const HALO_OBJECT_MAX_POOL_ENTRIES: usize = 2048;
 const HALO_PLAYER_MAX_POOL_ENTRIES: usize = 16;
 
+// Addresses of the engine globals for one build of the game.
+#[derive(Clone, Debug)]
+pub struct GameProfile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub object_pool_header_address: usize,
+    pub player_pool_header_address: usize,
+    pub tag_header_address: usize,
+    pub player_globals_address: usize,
+    // Not known for every build, the sampler searches guest RAM for it when missing.
+    pub game_time_globals_address: Option<usize>
+}
+
+pub const HALO_XBOX_RETAIL: GameProfile = GameProfile {
+    name: "halo-xbox-retail",
+    description: "Halo 1 Xbox Retail",
+    object_pool_header_address: HALO_OBJECT_POOL_HEADER_ADDR,
+    player_pool_header_address: HALO_PLAYER_POOL_HEADER_ADDR,
+    tag_header_address: HALO_TAG_HEADER_ADDR,
+    player_globals_address: HALO_PLAYER_GLOBALS_ADDR,
+    game_time_globals_address: None
+};
+
+pub const GAME_PROFILES: &[GameProfile] = &[HALO_XBOX_RETAIL];
+
+pub fn find_game_profile(name: &str) -> Option<&'static GameProfile> {
+    GAME_PROFILES.iter().find(|profile| profile.name == name)
+}
+
 // Sanity check constants
 const AT_T_AT_D: u32 = 1681945664;
 const DEAH: u32 = 1751474532;
@@ -22,9 +64,29 @@ const RNCS: u32 = 1935896178;
 
 // Halo Structs
 #[repr(C)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "DatumPair", from = "DatumPair")]
 pub struct DatumHandle(u32);
 
+// Serialized form of a datum handle.
+#[derive(Serialize, Deserialize)]
+struct DatumPair {
+    index: u16,
+    salt: u16
+}
+
+impl From<DatumHandle> for DatumPair {
+    fn from(handle: DatumHandle) -> DatumPair {
+        DatumPair { index: handle.get_index(), salt: handle.get_id() }
+    }
+}
+
+impl From<DatumPair> for DatumHandle {
+    fn from(pair: DatumPair) -> DatumHandle {
+        DatumHandle::new_from_index_id(pair.index, pair.salt)
+    }
+}
+
 impl DatumHandle {
     pub fn new_from_index_id(index: u16, id: u16) -> DatumHandle {
         DatumHandle { 0: ((id as u32) << 16) | index as u32 } 
@@ -60,7 +122,7 @@ impl fmt::Debug for DatumHandle {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct PlayersGlobals {
     pub unknown_1: i32,
@@ -74,13 +136,16 @@ pub struct PlayersGlobals {
     pub respawn_failure: u16,
     pub teleported: u8,
     pub unk_flags: u8,
+    #[serde(with = "hex_bytes")]
     pub combined_pvs: [u8; 0x40],
+    #[serde(with = "hex_bytes")]
     pub combined_pvs_local: [u8; 0x40]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct PlayerPoolHeader { // Same as the object pool header
+    #[serde(with = "hex_bytes")]
     pub name: [u8; 32],
     pub max_objects: u16,
     pub object_table_size: u16,
@@ -90,10 +155,11 @@ pub struct PlayerPoolHeader { // Same as the object pool header
     pub max_object_count: u16,
     pub current_objects: u16,
     pub next_object_id: u16,
+    #[serde(with = "hex_bytes")]
     pub object_data_begin: [u8; 4]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct PlayerPoolEntry {
     pub id: u16, // update rev github with this info
@@ -102,17 +168,23 @@ pub struct PlayerPoolEntry {
     pub unknown_1: [i32; 6],
     pub slave_unit_index: DatumHandle, // datum
     pub last_slave_unit_index: DatumHandle, // datum
+    #[serde(with = "hex_bytes")]
     pub unknown_2: [u8; 150]
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct TagEntry {
+    #[serde(with = "fourcc")]
     pub tag_class: [u8; 4],
+    #[serde(with = "fourcc")]
     pub tag_class_secondary: [u8; 4],
+    #[serde(with = "fourcc")]
     pub tag_class_tertiary: [u8; 4],
     pub tag_index: u32, // tag_id
+    #[serde(with = "hex_bytes")]
     tag_path_ptr: [u8; 4],
+    #[serde(with = "hex_bytes")]
     tag_data_ptr: [u8; 4],
     unknown_1: u32,
     unknown_2: u32
@@ -133,19 +205,33 @@ pub struct TagHeader {
     footer: u32 // tags backwards
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct ObjectPoolEntry {
     pub id: u16,
     pub unknown_1: u16, 
     pub unknown_2: u16,
     pub size: u16,
+    #[serde(with = "hex_bytes")]
     pub object_address: [u8; 4]
 }
 
-#[derive(Debug)]
+impl ObjectPoolEntry {
+    // Pool entries point 0x18 bytes into the object header.
+    pub fn game_object_address(&self) -> Option<usize> {
+        let base_pointer = guest_pointer(&self.object_address);
+        if base_pointer == 0 || base_pointer < 0x18 {
+            return None;
+        }
+
+        Some(base_pointer - 0x18)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct ObjectPoolHeader {
+    #[serde(with = "hex_bytes")]
     pub name: [u8; 32],
     pub max_objects: u16,
     pub object_table_size: u16,
@@ -155,10 +241,11 @@ pub struct ObjectPoolHeader {
     pub max_object_count: u16,
     pub current_objects: u16, // is this supposed to be here?
     pub next_object_id: u16,
+    #[serde(with = "hex_bytes")]
     pub object_data_begin: [u8; 4] // 32-bit pointer of 3 bytes?
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct GameObject {
     pub header_head: u32,
@@ -173,8 +260,34 @@ pub struct GameObject {
     pub position: [f32; 3]
 }
 
+// Offsets of the editable fields from the start of a GameObject.
+pub const GAME_OBJECT_FLAGS_OFFSET: usize = std::mem::offset_of!(GameObject, flags);
+pub const GAME_OBJECT_POSITION_OFFSET: usize = std::mem::offset_of!(GameObject, position);
+
 // Application
-#[derive(Debug)]
+// Field layout follows the PC build, the Xbox build is assumed to match.
+#[derive(Clone, Debug)]
+#[repr(C)]
+pub struct GameTimeGlobals {
+    pub initialized: u8,
+    pub active: u8,
+    pub paused: u8,
+    pub unknown_1: u8,
+    pub unknown_2: [i16; 4],
+    pub game_time: u32, // ticks since the map started
+    pub elapsed_time: u32,
+    pub unknown_3: u32,
+    pub game_speed: f32,
+    pub leftover_time: f32
+}
+
+impl GameTimeGlobals {
+    fn is_plausible(&self) -> bool {
+        self.initialized == 1 && self.active <= 1 && self.paused <= 1 && self.game_speed == 1.0
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct EngineSnapshot {
     pub object_pool_header: ObjectPoolHeader,
     pub object_pool_entries: Vec<Option<ObjectPoolEntry>>, 
@@ -183,7 +296,11 @@ pub struct EngineSnapshot {
     pub player_globals: PlayersGlobals,
     pub player_pool_entries: Vec<Option<PlayerPoolEntry>>,
     pub tags: HashMap<u32, String>,
-    pub tag_entries: HashMap<u32, TagEntry>
+    pub tag_entries: HashMap<u32, TagEntry>,
+    // Game tick the snapshot was taken on, when the game time globals are known.
+    // Snapshots saved before ticks were tracked load without one.
+    #[serde(default)]
+    pub game_tick: Option<u32>
 }
 
 impl EngineSnapshot {
@@ -199,6 +316,14 @@ impl EngineSnapshot {
         None
     }
 
+    // Tag path of the loaded scenario, which names the map.
+    pub fn scenario_path(&self) -> Option<&str> {
+        self.tag_entries.values()
+            .find(|tag_entry| u32::from_le_bytes(tag_entry.tag_class) == RNCS)
+            .and_then(|tag_entry| self.tags.get(&tag_entry.tag_index))
+            .map(|path| path.as_str())
+    }
+
     pub fn find_next_object_datum_player(&self, object_datum: DatumHandle) -> Option<usize> {
         for (index, player_object_datum_handle) in self.player_globals.local_dead_players.iter().enumerate() {
             if player_object_datum_handle.get_index() == object_datum.get_index() {
@@ -210,73 +335,228 @@ impl EngineSnapshot {
     }
 }
 
-pub fn build_snapshot(bytes: &[u8]) -> Option<EngineSnapshot> {
+// Engine structs whose fields cover every byte, viewing one of them as bytes never reads padding.
+// PlayerPoolEntry is padded at the end and has to be written field by field instead.
+pub unsafe trait Unpadded {}
+
+unsafe impl Unpadded for ObjectPoolHeader {}
+unsafe impl Unpadded for PlayerPoolHeader {}
+unsafe impl Unpadded for PlayersGlobals {}
+unsafe impl Unpadded for TagEntry {}
+unsafe impl Unpadded for ObjectPoolEntry {}
+unsafe impl Unpadded for GameObject {}
+
+// Sizes of the fields added up, a field change that introduces padding stops the build here.
+const _: () = assert!(size_of::<ObjectPoolHeader>() == 56);
+const _: () = assert!(size_of::<PlayerPoolHeader>() == 56);
+const _: () = assert!(size_of::<PlayersGlobals>() == 176);
+const _: () = assert!(size_of::<TagEntry>() == 32);
+const _: () = assert!(size_of::<ObjectPoolEntry>() == 12);
+const _: () = assert!(size_of::<GameObject>() == 48);
+
+// Raw view of one of the repr(C) engine structs, used to store them exactly as they were read from memory.
+pub fn struct_bytes<T: Unpadded>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+pub fn struct_from_bytes<T>(bytes: &[u8]) -> T {
+    assert!(bytes.len() >= size_of::<T>(), "Not enough bytes to read struct.");
+    unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) }
+}
+
+// Where the object pool signature lives in guest RAM, used to recognize guest RAM inside the emulator.
+pub fn engine_signature(profile: &GameProfile) -> (usize, u32) {
+    (profile.object_pool_header_address + std::mem::offset_of!(ObjectPoolHeader, signature), AT_T_AT_D)
+}
+
+// Guest pointers are stored as three bytes, the top byte is not part of the address.
+fn guest_pointer(bytes: &[u8; 4]) -> usize {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0x0]) as usize
+}
+
+fn read_struct<T, M: GuestMemory>(memory: &mut M, address: usize) -> Option<T> {
+    let mut bytes = vec![0u8; size_of::<T>()];
+    if !memory.read_into(address, &mut bytes) {
+        return None;
+    }
+
+    Some(struct_from_bytes(&bytes))
+}
+
+// Contiguous arrays are read in one go.
+fn read_structs<T, M: GuestMemory>(memory: &mut M, address: usize, count: usize) -> Option<Vec<T>> {
+    let mut bytes = vec![0u8; size_of::<T>() * count];
+    if !memory.read_into(address, &mut bytes) {
+        return None;
+    }
+
+    Some(bytes.chunks_exact(size_of::<T>()).map(struct_from_bytes).collect())
+}
+
+fn read_c_string<M: GuestMemory>(memory: &mut M, address: usize) -> Option<String> {
+    const CHUNK_SIZE: usize = 64;
+    const MAXIMUM_LENGTH: usize = 1024;
+
+    let mut bytes = Vec::new();
+    while bytes.len() < MAXIMUM_LENGTH {
+        let mut chunk = [0u8; CHUNK_SIZE];
+        if !memory.read_into(address + bytes.len(), &mut chunk) {
+            return None;
+        }
+
+        match chunk.iter().position(|byte| *byte == 0) {
+            Some(length) => {
+                bytes.extend_from_slice(&chunk[..length]);
+                return String::from_utf8(bytes).ok();
+            }
+            None => bytes.extend_from_slice(&chunk)
+        }
+    }
+
+    None
+}
+
+// Tag paths and entries only change when a map loads, so they are read once per map instead of every snapshot.
+pub struct TagCache {
+    // Tag array pointer, map id and tag count of the map the cache was filled from.
+    key: Option<(usize, u32, u32)>,
+    tags: HashMap<u32, String>,
+    tag_entries: HashMap<u32, TagEntry>
+}
+
+impl TagCache {
+    pub fn new() -> TagCache {
+        TagCache {
+            key: None,
+            tags: HashMap::new(),
+            tag_entries: HashMap::new()
+        }
+    }
+
+    fn refresh<M: GuestMemory>(&mut self, memory: &mut M, tag_header: &TagHeader) {
+        let key = (guest_pointer(&tag_header.tag_array_ptr), tag_header.map_id, tag_header.tag_count);
+        if self.key == Some(key) {
+            return;
+        }
+
+        self.key = Some(key);
+        self.tags.clear();
+        self.tag_entries.clear();
+
+        let tag_entries: Vec<TagEntry> = read_structs(memory, key.0, tag_header.tag_count as usize).unwrap_or_default();
+
+        // Also used for tag_entries as both will be treated seperately but same.
+        for tag_entry in tag_entries {
+            if !self.tags.contains_key(&tag_entry.tag_index) {
+                if let Some(value) = read_c_string(memory, guest_pointer(&tag_entry.tag_path_ptr)) {
+                    self.tags.insert(tag_entry.tag_index, value);
+                }
+
+                self.tag_entries.insert(tag_entry.tag_index, tag_entry);
+            }
+        }
+    }
+}
+
+// Build a snapshot from a full copy of guest RAM, e.g. a RAM dump.
+pub fn build_snapshot(bytes: &[u8], profile: &GameProfile) -> Option<EngineSnapshot> {
+    let mut memory = bytes;
+    read_snapshot(&mut memory, profile, &mut TagCache::new())
+}
+
+// Find where the object a datum refers to lives right now, checking the slot still holds that datum and the object header is intact.
+// Writes go through this so an edit made from an old snapshot can not land in an object that has since taken the slot.
+pub fn locate_object<M: GuestMemory>(memory: &mut M, profile: &GameProfile, datum: &DatumHandle) -> Result<usize, String> {
+    locate_object_tag(memory, profile, datum).map(|(address, _)| address)
+}
+
+// Like locate_object, also returning the tag index of the object.
+pub fn locate_object_tag<M: GuestMemory>(memory: &mut M, profile: &GameProfile, datum: &DatumHandle) -> Result<(usize, u32), String> {
+    let (_, address, game_object) = read_object_slot(memory, profile, datum.get_index(), Some(datum.get_id()))?;
+    Ok((address, game_object.tag_index))
+}
+
+// Find the object in a slot by its tag instead of its salt, for writes recorded before the game was restarted.
+// Returns the datum the slot holds now.
+pub fn locate_slot_object<M: GuestMemory>(memory: &mut M, profile: &GameProfile, index: u16, tag_index: u32) -> Result<(DatumHandle, usize), String> {
+    let (datum, address, game_object) = read_object_slot(memory, profile, index, None)?;
+    if game_object.tag_index != tag_index {
+        return Err(format!("Slot {} holds tag {:#X} instead of {:#X}.", index, game_object.tag_index, tag_index));
+    }
+
+    Ok((datum, address))
+}
+
+// The object in slot `index`, when `id` is given the slot has to still hold it.
+fn read_object_slot<M: GuestMemory>(memory: &mut M, profile: &GameProfile, index: u16, id: Option<u16>) -> Result<(DatumHandle, usize, GameObject), String> {
+    let pool_header: ObjectPoolHeader = read_struct(memory, profile.object_pool_header_address).ok_or("Could not read the object pool header.")?;
+    if pool_header.signature != AT_T_AT_D {
+        return Err("The object pool is not valid, the map may be loading.".to_string());
+    }
+
+    if index >= pool_header.max_objects {
+        return Err(format!("Slot {} is outside the object pool.", index));
+    }
+
+    let entry_address = guest_pointer(&pool_header.object_data_begin) + index as usize * size_of::<ObjectPoolEntry>();
+    let pool_entry: ObjectPoolEntry = read_struct(memory, entry_address).ok_or(format!("Could not read slot {}.", index))?;
+    if let Some(id) = id {
+        if pool_entry.id != id {
+            return Err(format!("Slot {} was reused, it holds ID {} instead of {}.", index, pool_entry.id, id));
+        }
+    }
+
+    let address = pool_entry.game_object_address().ok_or(format!("Slot {} does not point at an object.", index))?;
+    let game_object: GameObject = read_struct(memory, address).ok_or(format!("Could not read the object in slot {}.", index))?;
+    if game_object.header_head != DEAH || game_object.header_tail != LIAT {
+        return Err(format!("The object in slot {} is missing its DEAH/LIAT markers.", index));
+    }
+
+    Ok((DatumHandle::new_from_index_id(index, pool_entry.id), address, game_object))
+}
+
+// Build a snapshot reading only the parts of guest RAM the engine structures point at.
+pub fn read_snapshot<M: GuestMemory>(memory: &mut M, profile: &GameProfile, tag_cache: &mut TagCache) -> Option<EngineSnapshot> {
     // Headers
-    let pool_header: ObjectPoolHeader = unsafe { std::ptr::read(bytes[HALO_OBJECT_POOL_HEADER_ADDR..].as_ptr() as *const _) };
+    let pool_header: ObjectPoolHeader = read_struct(memory, profile.object_pool_header_address)?;
     if pool_header.signature != AT_T_AT_D { return None; }
 
-    let tag_header: TagHeader = unsafe { std::ptr::read(bytes[HALO_TAG_HEADER_ADDR..].as_ptr() as *const _) };
+    let tag_header: TagHeader = read_struct(memory, profile.tag_header_address)?;
     if tag_header.footer != RNCS { return None; }
 
-    let player_pool_header: PlayerPoolHeader = unsafe { std::ptr::read(bytes[HALO_PLAYER_POOL_HEADER_ADDR..].as_ptr() as *const _) };
+    let player_pool_header: PlayerPoolHeader = read_struct(memory, profile.player_pool_header_address)?;
     if player_pool_header.signature != AT_T_AT_D { return None; } 
 
     // TODO: Find a way to sanity check this data
-    let player_globals: PlayersGlobals = unsafe { std::ptr::read(bytes[HALO_PLAYER_GLOBALS_ADDR..].as_ptr() as *const _) };
+    let player_globals: PlayersGlobals = read_struct(memory, profile.player_globals_address)?;
 
     let mut game_object_entries: Vec<_> = (0..HALO_OBJECT_MAX_POOL_ENTRIES).map(|_| None).collect();
     let mut object_pool_entries: Vec<_> = (0..HALO_OBJECT_MAX_POOL_ENTRIES).map(|_| None).collect();
     let mut player_pool_entries: Vec<_> = (0..HALO_PLAYER_MAX_POOL_ENTRIES).map(|_| None).collect();
-    
-    let object_data_begin = u32::from_le_bytes([pool_header.object_data_begin[0], pool_header.object_data_begin[1], pool_header.object_data_begin[2], 0x0]);
-    for index in (0..pool_header.max_objects as usize).rev() {
-        let index_ptr_addr =  object_data_begin as usize + (size_of::<ObjectPoolEntry>() * index);
-        let pool_entry: ObjectPoolEntry = unsafe { std::ptr::read(bytes[index_ptr_addr..].as_ptr() as *const _) };
-
-        let base_pointer = u32::from_le_bytes([pool_entry.object_address[0], pool_entry.object_address[1], pool_entry.object_address[2], 0x0]);
-
-        if pool_entry.id != 0 && base_pointer != 0 && base_pointer >= 0x18 {
-            let game_object_pointer = base_pointer as usize - 0x18;
-            let game_object_slice = &bytes[game_object_pointer..];
-            let game_object: GameObject = unsafe { std::ptr::read(game_object_slice.as_ptr() as *const _) };
-
-            if game_object.header_head == DEAH && game_object.header_tail == LIAT {
-                game_object_entries[index] = Some(game_object);
-                object_pool_entries[index] = Some(pool_entry);
-            }
-        }
-    }
 
-    // Get tag index mappings to tag names
-    // Also store the tag entries
-    let mut tag_index_to_tag_entry: HashMap<u32, TagEntry> = HashMap::new();
-    let mut tag_index_to_str: HashMap<u32, String> = HashMap::new();
-
-    let tag_array_base_ptr = u32::from_le_bytes([tag_header.tag_array_ptr[0], tag_header.tag_array_ptr[1], tag_header.tag_array_ptr[2], 0x0]);
-
-    for index in 0..tag_header.tag_count as usize {
-        let tag_entry_ptr =  tag_array_base_ptr as usize + (size_of::<TagEntry>() * index);
-        let tag_entry: TagEntry = unsafe { std::ptr::read(bytes[tag_entry_ptr..].as_ptr() as *const _) };
+    let pool_entries: Vec<ObjectPoolEntry> = read_structs(memory, guest_pointer(&pool_header.object_data_begin), pool_header.max_objects as usize)?;
+    for (index, pool_entry) in pool_entries.into_iter().enumerate().take(HALO_OBJECT_MAX_POOL_ENTRIES).rev() {
+        if pool_entry.id == 0 {
+            continue;
+        }
 
-        // Also used for tag_index_to_tag_entry as both will be treated seperately but same.
-        if !tag_index_to_str.contains_key(&tag_entry.tag_index) {
-            let tag_path_ptr = u32::from_le_bytes([tag_entry.tag_path_ptr[0], tag_entry.tag_path_ptr[1], tag_entry.tag_path_ptr[2], 0x0]) as usize;
-            unsafe { 
-                if let Ok(value) = CStr::from_ptr(bytes[tag_path_ptr..].as_ptr() as *const _).to_str() {
-                    tag_index_to_str.insert(tag_entry.tag_index, value.to_string());
+        if let Some(game_object_pointer) = pool_entry.game_object_address() {
+            if let Some(game_object) = read_struct::<GameObject, M>(memory, game_object_pointer) {
+                if game_object.header_head == DEAH && game_object.header_tail == LIAT {
+                    game_object_entries[index] = Some(game_object);
+                    object_pool_entries[index] = Some(pool_entry);
                 }
             }
-
-            tag_index_to_tag_entry.insert(tag_entry.tag_index, tag_entry);
         }
     }
 
-    // Player Pool Entries
-    let player_object_data_begin = u32::from_le_bytes([player_pool_header.object_data_begin[0], player_pool_header.object_data_begin[1], player_pool_header.object_data_begin[2], 0x0]);
-    for index in (0..player_pool_header.max_objects as usize).rev() {
-        let index_ptr_addr =  player_object_data_begin as usize + (size_of::<PlayerPoolEntry>() * index);
-        let player_pool_entry: PlayerPoolEntry = unsafe { std::ptr::read(bytes[index_ptr_addr..].as_ptr() as *const _) };
+    // Get tag index mappings to tag names
+    // Also store the tag entries
+    tag_cache.refresh(memory, &tag_header);
 
+    // Player Pool Entries
+    let player_entries: Vec<PlayerPoolEntry> = read_structs(memory, guest_pointer(&player_pool_header.object_data_begin), player_pool_header.max_objects as usize)?;
+    for (index, player_pool_entry) in player_entries.into_iter().enumerate().take(HALO_PLAYER_MAX_POOL_ENTRIES).rev() {
         if player_pool_entry.id != 0 {
             player_pool_entries[index] = Some(player_pool_entry);
         }
@@ -289,7 +569,44 @@ pub fn build_snapshot(bytes: &[u8]) -> Option<EngineSnapshot> {
         player_pool_header: player_pool_header,
         player_pool_entries: player_pool_entries,
         player_globals: player_globals,
-        tags: tag_index_to_str,
-        tag_entries: tag_index_to_tag_entry
+        tags: tag_cache.tags.clone(),
+        tag_entries: tag_cache.tag_entries.clone(),
+        game_tick: None
     })
 }
+
+pub fn read_game_tick<M: GuestMemory>(memory: &mut M, address: usize) -> Option<u32> {
+    let globals: GameTimeGlobals = read_struct(memory, address)?;
+    if !globals.is_plausible() { return None; }
+
+    Some(globals.game_time)
+}
+
+// Compare two copies of guest RAM taken `elapsed` apart, both starting at address 0, and return every
+// address holding plausible game time globals whose game time advanced at the tick rate in between.
+pub fn find_game_time_globals(first: &[u8], second: &[u8], elapsed: Duration) -> Vec<usize> {
+    let expected = elapsed.as_secs_f32() * GAME_TICKS_PER_SECOND as f32;
+    let tolerance = expected * 0.25 + 2.0;
+    let length = first.len().min(second.len());
+
+    let mut candidates = Vec::new();
+    if length < size_of::<GameTimeGlobals>() {
+        return candidates;
+    }
+
+    for address in (0..=length - size_of::<GameTimeGlobals>()).step_by(4) {
+        let before: GameTimeGlobals = struct_from_bytes(&first[address..]);
+        let after: GameTimeGlobals = struct_from_bytes(&second[address..]);
+
+        if !before.is_plausible() || !after.is_plausible() || before.paused != 0 || after.paused != 0 {
+            continue;
+        }
+
+        let advanced = after.game_time.wrapping_sub(before.game_time) as f32;
+        if (advanced - expected).abs() <= tolerance {
+            candidates.push(address);
+        }
+    }
+
+    candidates
+}
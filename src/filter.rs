@@ -0,0 +1,229 @@
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{load_json, save_json},
+    engine::EngineSnapshot,
+    rows::ObjectRow
+};
+
+pub const FILTER_PRESETS_FILE: &str = "object-filters.json";
+
+pub const SLOT_FILTERS: [&str; 3] = ["All Slots", "Occupied", "Free"];
+const SLOTS_OCCUPIED: usize = 1;
+const SLOTS_FREE: usize = 2;
+
+// What the Objects table shows, every part left empty or off matches everything.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectFilter {
+    // Matched against the datum, index, ID, tag path and tag classes.
+    pub search: String,
+    // Four character code matched against the primary, secondary and tertiary tag class.
+    pub tag_class: String,
+    pub tag_path: String,
+    pub tag_path_regex: bool,
+    pub slots: usize,
+    // Only units controlled by or reserved for a local player.
+    pub players_only: bool,
+    pub first_index: i32,
+    pub last_index: i32,
+    // Local player to measure from, or -1.
+    pub distance_player: i32,
+    pub max_distance: f32
+}
+
+impl Default for ObjectFilter {
+    fn default() -> ObjectFilter {
+        ObjectFilter {
+            search: String::default(),
+            tag_class: String::default(),
+            tag_path: String::default(),
+            tag_path_regex: false,
+            slots: 0,
+            players_only: false,
+            first_index: 0,
+            last_index: 2047,
+            distance_player: -1,
+            max_distance: 10.0
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FilterPreset {
+    pub name: String,
+    pub filter: ObjectFilter
+}
+
+pub fn load_filter_presets(path: &Path) -> Vec<FilterPreset> {
+    load_json(path).unwrap_or_else(|e| {
+        println!("{}", e);
+        Vec::new()
+    })
+}
+
+pub fn save_filter_presets(path: &Path, presets: &[FilterPreset]) -> Result<(), String> {
+    save_json(path, presets)
+}
+
+// The tag path regex is only compiled again when its pattern changes.
+pub struct RegexCache {
+    pattern: String,
+    regex: Option<Result<Regex, String>>
+}
+
+impl RegexCache {
+    pub fn new() -> RegexCache {
+        RegexCache {
+            pattern: String::default(),
+            regex: None
+        }
+    }
+
+    fn get(&mut self, pattern: &str) -> Result<Regex, String> {
+        if self.regex.is_none() || self.pattern != pattern {
+            self.pattern = pattern.to_string();
+            self.regex = Some(RegexBuilder::new(pattern).case_insensitive(true).build().map_err(|e| e.to_string()));
+        }
+
+        self.regex.clone().unwrap()
+    }
+}
+
+enum TagPathMatch {
+    Any,
+    Substring(String),
+    Regex(Regex)
+}
+
+// An ObjectFilter prepared for one snapshot.
+pub struct ObjectMatcher {
+    search: String,
+    tag_class: String,
+    tag_path: TagPathMatch,
+    slots: usize,
+    players_only: bool,
+    first_index: usize,
+    last_index: usize,
+    // Position of the player to measure from and the largest distance allowed.
+    distance: Option<([f32; 3], f32)>
+}
+
+impl ObjectFilter {
+    pub fn is_active(&self) -> bool {
+        let default = ObjectFilter::default();
+        !self.search.is_empty() || !self.tag_class.is_empty() || !self.tag_path.is_empty() || self.slots != default.slots || self.players_only
+            || self.first_index != default.first_index || self.last_index != default.last_index || self.distance_player >= 0
+    }
+
+    // Fails when the tag path regex does not compile or the player to measure from has no unit.
+    pub fn matcher(&self, snapshot: &EngineSnapshot, regex_cache: &mut RegexCache) -> Result<ObjectMatcher, String> {
+        let tag_path = if self.tag_path.is_empty() {
+            TagPathMatch::Any
+        } else if self.tag_path_regex {
+            TagPathMatch::Regex(regex_cache.get(&self.tag_path)?)
+        } else {
+            TagPathMatch::Substring(self.tag_path.to_lowercase())
+        };
+
+        let distance = if self.distance_player >= 0 {
+            let position = player_position(snapshot, self.distance_player as usize)
+                .ok_or(format!("Player {} has no unit to measure from.", self.distance_player))?;
+            Some((position, self.max_distance))
+        } else {
+            None
+        };
+
+        Ok(ObjectMatcher {
+            search: self.search.trim().to_lowercase(),
+            tag_class: self.tag_class.trim().to_lowercase(),
+            tag_path: tag_path,
+            slots: self.slots,
+            players_only: self.players_only,
+            first_index: self.first_index.max(0) as usize,
+            last_index: self.last_index.max(0) as usize,
+            distance: distance
+        })
+    }
+}
+
+impl ObjectMatcher {
+    // `row` is None for a free slot.
+    pub fn matches(&self, index: usize, row: Option<&ObjectRow>) -> bool {
+        if index < self.first_index || index > self.last_index {
+            return false;
+        }
+
+        let row = match (row, self.slots) {
+            (Some(_), SLOTS_FREE) | (None, SLOTS_OCCUPIED) => return false,
+            (Some(row), _) => row,
+            // Only the index can match a free slot.
+            (None, _) => {
+                let object_filters = !self.tag_class.is_empty() || !matches!(self.tag_path, TagPathMatch::Any) || self.players_only || self.distance.is_some();
+                return !object_filters && (self.search.is_empty() || index.to_string().contains(&self.search));
+            }
+        };
+
+        if !self.tag_class.is_empty() && ![&row.tag_class, &row.tag_class_secondary, &row.tag_class_tertiary].iter().any(|class| class.to_lowercase() == self.tag_class) {
+            return false;
+        }
+
+        let tag_path_matches = match &self.tag_path {
+            TagPathMatch::Any => true,
+            TagPathMatch::Substring(text) => row.tag_name.to_lowercase().contains(text),
+            TagPathMatch::Regex(regex) => regex.is_match(&row.tag_name)
+        };
+
+        if !tag_path_matches {
+            return false;
+        }
+
+        if self.players_only && row.player.is_none() && row.dead_player.is_none() {
+            return false;
+        }
+
+        if let Some((origin, max_distance)) = self.distance {
+            if distance(origin, row.position) > max_distance {
+                return false;
+            }
+        }
+
+        if self.search.is_empty() {
+            return true;
+        }
+
+        let fields = [
+            row.datum.get_handle().to_string(),
+            row.index.to_string(),
+            row.id.to_string(),
+            row.tag_name.to_lowercase(),
+            row.tag_class.to_lowercase(),
+            row.tag_class_secondary.to_lowercase(),
+            row.tag_class_tertiary.to_lowercase()
+        ];
+
+        fields.iter().any(|field| field.contains(&self.search))
+    }
+}
+
+// Position of the unit a local player controls.
+pub fn player_position(snapshot: &EngineSnapshot, local_player: usize) -> Option<[f32; 3]> {
+    let player = snapshot.player_globals.local_players.get(local_player)?;
+    if player.is_invald() {
+        return None;
+    }
+
+    let unit = &snapshot.player_pool_entries.get(player.get_index() as usize)?.as_ref()?.slave_unit_index;
+    if unit.is_invald() {
+        return None;
+    }
+
+    snapshot.game_object_entries.get(unit.get_index() as usize)?.as_ref().map(|game_object| game_object.position)
+}
+
+pub fn distance(first: [f32; 3], second: [f32; 3]) -> f32 {
+    ((first[0] - second[0]).powi(2) + (first[1] - second[1]).powi(2) + (first[2] - second[2]).powi(2)).sqrt()
+}
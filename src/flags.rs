@@ -0,0 +1,38 @@
+// Bits of GameObject.flags. The names come from research on the PC build and have not all been confirmed on Xbox,
+// every other bit is shown as unknown.
+const OBJECT_FLAG_NAMES: [(u32, &str); 13] = [
+    (0, "Hidden"),
+    (1, "On Ground"),
+    (2, "Ignore Gravity"),
+    (3, "In Water"),
+    (4, "At Rest"),
+    (6, "No Collision"),
+    (7, "Looping Sound Attached"),
+    (8, "Connected To Map"),
+    (9, "Not Placed Automatically"),
+    (13, "Garbage"),
+    (15, "Does Not Cast Shadow"),
+    (16, "Deactivation Is Deletion"),
+    (18, "Outside Of Map")
+];
+
+pub const OBJECT_FLAG_BITS: u32 = u32::BITS;
+
+pub fn object_flag_name(bit: u32) -> String {
+    match OBJECT_FLAG_NAMES.iter().find(|(named_bit, _)| *named_bit == bit) {
+        Some((_, name)) => name.to_string(),
+        None => format!("Unknown {}", bit)
+    }
+}
+
+pub fn is_flag_set(flags: u32, bit: u32) -> bool {
+    flags & (1 << bit) != 0
+}
+
+// Names of the bits set in `flags`, lowest bit first.
+pub fn set_flag_names(flags: u32) -> Vec<String> {
+    (0..OBJECT_FLAG_BITS)
+        .filter(|bit| is_flag_set(flags, *bit))
+        .map(object_flag_name)
+        .collect()
+}
@@ -0,0 +1,133 @@
+use crate::{
+    engine::{locate_object, DatumHandle, EngineSnapshot, GameProfile, GAME_OBJECT_FLAGS_OFFSET, GAME_OBJECT_POSITION_OFFSET},
+    memory::{DirectMemory, ProcessMemory}
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FreezeField {
+    Position,
+    Flags,
+    // Any offset from the start of the GameObject, e.g. a field that is not decoded yet.
+    Custom(usize)
+}
+
+impl FreezeField {
+    pub fn offset(&self) -> usize {
+        match self {
+            FreezeField::Position => GAME_OBJECT_POSITION_OFFSET,
+            FreezeField::Flags => GAME_OBJECT_FLAGS_OFFSET,
+            FreezeField::Custom(offset) => *offset
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            FreezeField::Position => "Position".to_string(),
+            FreezeField::Flags => "Flags".to_string(),
+            FreezeField::Custom(offset) => format!("+{:#X}", offset)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum FreezeTarget {
+    // A field of the object in a pool slot, only while the slot holds the same datum.
+    Object { datum: DatumHandle, field: FreezeField },
+    // A raw physical guest address.
+    Address(usize)
+}
+
+pub struct FrozenValue {
+    pub label: String,
+    pub target: FreezeTarget,
+    pub bytes: Vec<u8>,
+    pub enabled: bool
+}
+
+// Values written back into guest memory after every sample, so the game can not change them.
+pub struct FreezeList {
+    pub values: Vec<FrozenValue>,
+    // Freezes dropped because their slot was reused, shown until the list is changed again.
+    pub dropped: Vec<String>
+}
+
+impl FreezeList {
+    pub fn new() -> FreezeList {
+        FreezeList {
+            values: Vec::new(),
+            dropped: Vec::new()
+        }
+    }
+
+    pub fn add(&mut self, label: String, target: FreezeTarget, bytes: Vec<u8>) {
+        self.dropped.clear();
+        self.values.push(FrozenValue {
+            label: label,
+            target: target,
+            bytes: bytes,
+            enabled: true
+        });
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.dropped.clear();
+        self.values.remove(index);
+    }
+
+    pub fn find_object(&self, datum: &DatumHandle, field: FreezeField) -> Option<usize> {
+        self.values.iter().position(|value| match &value.target {
+            FreezeTarget::Object { datum: frozen_datum, field: frozen_field } => frozen_datum.get_handle() == datum.get_handle() && *frozen_field == field,
+            FreezeTarget::Address(_) => false
+        })
+    }
+
+    // Write every enabled value again, dropping object freezes whose slot no longer holds the frozen datum.
+    // Objects are looked up again right before the write, a slot reused since the snapshot is skipped until the next one drops it.
+    pub fn apply(&mut self, memory: &mut ProcessMemory, profile: &GameProfile, snapshot: &EngineSnapshot) {
+        let mut dropped = Vec::new();
+
+        self.values.retain(|value| {
+            let address = match &value.target {
+                FreezeTarget::Object { datum, field } => {
+                    let index = datum.get_index() as usize;
+                    let entry = snapshot.object_pool_entries.get(index).and_then(|entry| entry.as_ref());
+
+                    match entry {
+                        Some(entry) if entry.id == datum.get_id() => {
+                            locate_object(&mut DirectMemory(memory), profile, datum).ok().map(|address| address + field.offset())
+                        }
+                        _ => {
+                            println!("Slot {} no longer holds datum {}, dropped freeze \"{}\".", index, datum.get_handle(), value.label);
+                            dropped.push(value.label.clone());
+                            return false;
+                        }
+                    }
+                }
+                FreezeTarget::Address(address) => Some(*address)
+            };
+
+            if let Some(address) = address {
+                if value.enabled {
+                    memory.write(address, &value.bytes);
+                }
+            }
+
+            true
+        });
+
+        self.dropped.extend(dropped);
+    }
+}
+
+// Bytes typed as hex, spaces between bytes are allowed.
+pub fn parse_hex_bytes(text: &str) -> Option<Vec<u8>> {
+    let digits: String = text.chars().filter(|character| !character.is_whitespace()).collect();
+    if digits.is_empty() || digits.len() % 2 != 0 || !digits.is_ascii() {
+        return None;
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use crate::{diff::SnapshotEvent, engine::{DatumHandle, EngineSnapshot}};
+
+// The garbage collector frees a batch of objects in a single tick, normal gameplay
+// rarely deletes this many objects between two snapshots.
+pub const GC_MINIMUM_DELETIONS: usize = 4;
+
+#[derive(Debug)]
+pub struct CollectedObject {
+    pub datum: DatumHandle,
+    pub tag_path: String,
+    pub player_referenced: bool
+}
+
+#[derive(Debug)]
+pub struct GcEvent {
+    pub timestamp: Duration,
+    // Game tick of the snapshot the objects were missing from.
+    pub tick: Option<u32>,
+    pub objects: Vec<CollectedObject>
+}
+
+impl GcEvent {
+    pub fn freed_player_datum(&self) -> bool {
+        self.objects.iter().any(|object| object.player_referenced)
+    }
+}
+
+pub struct GcLog {
+    pub minimum_deletions: usize,
+    pub events: Vec<GcEvent>
+}
+
+impl GcLog {
+    pub fn new() -> GcLog {
+        GcLog {
+            minimum_deletions: GC_MINIMUM_DELETIONS,
+            events: Vec::new()
+        }
+    }
+
+    pub fn process(&mut self, previous: &EngineSnapshot, current: &EngineSnapshot, events: &[SnapshotEvent], timestamp: Duration) -> Option<&GcEvent> {
+        let objects: Vec<_> = events.iter()
+            .filter_map(|event| event.freed_datum())
+            .map(|(datum, tag_index)| CollectedObject {
+                datum: datum.clone(),
+                tag_path: previous.tags.get(&tag_index).cloned().unwrap_or("UNKNOWN".to_string()),
+                player_referenced: is_player_referenced(previous, datum)
+            })
+            .collect();
+
+        if objects.len() < self.minimum_deletions {
+            return None;
+        }
+
+        self.events.push(GcEvent {
+            timestamp: timestamp,
+            tick: current.game_tick,
+            objects: objects
+        });
+
+        self.events.last()
+    }
+}
+
+fn is_player_referenced(snapshot: &EngineSnapshot, datum: &DatumHandle) -> bool {
+    let referenced_by_player = snapshot.player_pool_entries.iter().flatten().any(|player| {
+        player.slave_unit_index == *datum || player.last_slave_unit_index == *datum
+    });
+
+    referenced_by_player || snapshot.player_globals.local_dead_players.contains(datum)
+}
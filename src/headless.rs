@@ -0,0 +1,251 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::Instant
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    cli::{CsvTable, Options, OutputFormat},
+    engine::{engine_signature, read_snapshot, DatumHandle, EngineSnapshot, GameProfile, PlayerPoolEntry, TagCache},
+    memory::{find_guest_ram, find_single_process, ProcessMemory},
+    playback::FrameReader,
+    rows::object_rows,
+    serialization::snapshot_to_json
+};
+
+enum MemorySource {
+    Process(ProcessMemory, TagCache, &'static GameProfile),
+    // Snapshots, RAM dumps and sessions, every frame is printed once.
+    Dump(FrameReader)
+}
+
+impl MemorySource {
+    fn open(options: &Options) -> Result<MemorySource, String> {
+        if let Some(dump) = &options.dump {
+            let frames = FrameReader::open(dump, options.profile).map_err(|e| format!("Could not open {}: {}", dump.display(), e))?;
+            return Ok(MemorySource::Dump(frames));
+        }
+
+        // Ticks are only found by watching the game time for a while, which the viewer's sampler does.
+        if options.tick_sync {
+            return Err("--tick-sync is not supported in headless mode.".to_string());
+        }
+
+        let pid = match options.pid {
+            Some(pid) => pid,
+            None => find_single_process(&options.process)?
+        };
+
+        let address = match options.address {
+            Some(address) => address,
+            None => {
+                let (offset, signature) = engine_signature(options.profile);
+                find_guest_ram(pid, offset, signature).ok_or(format!("Could not find guest RAM in process {}, pass --address.", pid))?
+            }
+        };
+
+        Ok(MemorySource::Process(ProcessMemory::new(address, pid, true)?, TagCache::new(), options.profile))
+    }
+
+    // None once a dump has no frames left, a process can always be read again.
+    fn snapshot(&mut self, suspend: bool) -> Result<Option<Option<EngineSnapshot>>, String> {
+        match self {
+            MemorySource::Process(memory, tag_cache, profile) => {
+                let suspended = suspend.then(|| memory.suspend());
+                memory.begin_sample();
+                let snapshot = read_snapshot(memory, profile, tag_cache);
+                drop(suspended);
+
+                Ok(Some(snapshot))
+            }
+            MemorySource::Dump(frames) => frames.next_frame().map_err(|e| e.to_string())
+        }
+    }
+}
+
+pub fn run(options: &Options) -> Result<(), String> {
+    let mut source = MemorySource::open(options)?;
+    let started = Instant::now();
+    let mut stdout = io::stdout().lock();
+    let mut wrote_header = false;
+
+    loop {
+        let timestamp = started.elapsed().as_millis() as u64;
+
+        let snapshot = match source.snapshot(options.suspend_while_reading)? {
+            Some(snapshot) => snapshot,
+            None => return Ok(())
+        };
+
+        match snapshot {
+            Some(snapshot) => {
+                let output = match options.format {
+                    OutputFormat::Json => format!("{:#}\n", snapshot_json(&snapshot, timestamp)),
+                    OutputFormat::Ndjson => format!("{}\n", snapshot_json(&snapshot, timestamp)),
+                    OutputFormat::Snapshot => format!("{}\n", snapshot_to_json(&snapshot)?),
+                    OutputFormat::Csv => {
+                        let csv = snapshot_csv(&snapshot, options.table, timestamp, !wrote_header);
+                        wrote_header = true;
+                        csv
+                    }
+                };
+
+                stdout.write_all(output.as_bytes()).and_then(|_| stdout.flush()).map_err(|e| e.to_string())?;
+            }
+            None => eprintln!("Could not find a valid snapshot in guest memory."),
+        }
+
+        // A process is read once unless --interval is given, a dump goes through every frame and --interval only paces them.
+        match (options.interval, &source) {
+            (Some(interval), _) => thread::sleep(interval),
+            (None, MemorySource::Process(..)) => return Ok(()),
+            (None, MemorySource::Dump(_)) => {}
+        }
+    }
+}
+
+fn datum_json(datum: &DatumHandle) -> Value {
+    json!({
+        "handle": datum.get_handle(),
+        "index": datum.get_index(),
+        "id": datum.get_id()
+    })
+}
+
+fn player_name(player: &PlayerPoolEntry) -> String {
+    let length = player.player_name.iter().position(|c| *c == 0).unwrap_or(player.player_name.len());
+    String::from_utf16_lossy(&player.player_name[..length])
+}
+
+fn snapshot_json(snapshot: &EngineSnapshot, timestamp: u64) -> Value {
+    let objects: Vec<_> = object_rows(snapshot).iter().map(|row| json!({
+        "datum": datum_json(&row.datum),
+        "index": row.index,
+        "id": row.id,
+        "player": row.player,
+        "dead_player": row.dead_player,
+        "coordinates": row.position,
+        "tag_name": row.tag_name,
+        "tag_class": row.tag_class,
+        "tag_class_secondary": row.tag_class_secondary,
+        "tag_class_tertiary": row.tag_class_tertiary
+    })).collect();
+
+    let players: Vec<_> = snapshot.player_pool_entries.iter().enumerate()
+        .filter_map(|(index, player)| player.as_ref().map(|player| (index, player)))
+        .map(|(index, player)| json!({
+            "index": index,
+            "id": player.id,
+            "local_player_index": player.local_player_index,
+            "name": player_name(player),
+            "slave_unit": datum_json(&player.slave_unit_index),
+            "last_slave_unit": datum_json(&player.last_slave_unit_index)
+        }))
+        .collect();
+
+    let globals = &snapshot.player_globals;
+
+    json!({
+        "timestamp_ms": timestamp,
+        "game_tick": snapshot.game_tick,
+        "next_object_index": snapshot.object_pool_header.next_object_index,
+        "next_object_id": snapshot.object_pool_header.next_object_id,
+        "objects": objects,
+        "players": players,
+        "player_globals": {
+            "local_players": globals.local_players.iter().map(datum_json).collect::<Vec<_>>(),
+            "local_dead_players": globals.local_dead_players.iter().map(datum_json).collect::<Vec<_>>(),
+            "local_player_count": globals.local_player_count,
+            "double_speed_ticks_remaining": globals.double_speed_ticks_remaining,
+            "are_all_dead": globals.are_all_dead,
+            "input_disabled": globals.input_disabled,
+            "respawn_failure": globals.respawn_failure,
+            "teleported": globals.teleported
+        }
+    })
+}
+
+fn csv_field(value: impl ToString) -> String {
+    let value = value.to_string();
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn csv_optional(value: Option<usize>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn snapshot_csv(snapshot: &EngineSnapshot, table: CsvTable, timestamp: u64, header: bool) -> String {
+    let mut lines = Vec::new();
+
+    match table {
+        CsvTable::Objects => {
+            if header {
+                lines.push("timestamp_ms,datum,index,id,player,dead_player,x,y,z,tag_name,tag_class,tag_class_secondary,tag_class_tertiary".to_string());
+            }
+
+            for row in object_rows(snapshot) {
+                lines.push([
+                    timestamp.to_string(),
+                    row.datum.get_handle().to_string(),
+                    row.index.to_string(),
+                    row.id.to_string(),
+                    csv_optional(row.player),
+                    csv_optional(row.dead_player),
+                    row.position[0].to_string(),
+                    row.position[1].to_string(),
+                    row.position[2].to_string(),
+                    csv_field(&row.tag_name),
+                    csv_field(&row.tag_class),
+                    csv_field(&row.tag_class_secondary),
+                    csv_field(&row.tag_class_tertiary)
+                ].join(","));
+            }
+        }
+        CsvTable::Players => {
+            if header {
+                lines.push("timestamp_ms,index,id,local_player_index,name,slave_unit,last_slave_unit".to_string());
+            }
+
+            for (index, player) in snapshot.player_pool_entries.iter().enumerate() {
+                if let Some(player) = player {
+                    lines.push([
+                        timestamp.to_string(),
+                        index.to_string(),
+                        player.id.to_string(),
+                        player.local_player_index.to_string(),
+                        csv_field(player_name(player)),
+                        player.slave_unit_index.get_handle().to_string(),
+                        player.last_slave_unit_index.get_handle().to_string()
+                    ].join(","));
+                }
+            }
+        }
+        CsvTable::Globals => {
+            if header {
+                lines.push("timestamp_ms,respawn_failure,are_all_dead,input_disabled,teleported,local_player_count,local_players,local_dead_players".to_string());
+            }
+
+            let globals = &snapshot.player_globals;
+            let handles = |datums: &[DatumHandle]| datums.iter().map(|datum| datum.get_handle().to_string()).collect::<Vec<_>>().join(" ");
+
+            lines.push([
+                timestamp.to_string(),
+                globals.respawn_failure.to_string(),
+                globals.are_all_dead.to_string(),
+                globals.input_disabled.to_string(),
+                globals.teleported.to_string(),
+                globals.local_player_count.to_string(),
+                handles(&globals.local_players),
+                handles(&globals.local_dead_players)
+            ].join(","));
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
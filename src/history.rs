@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use crate::engine::{DatumHandle, EngineSnapshot};
+
+#[derive(Debug)]
+pub struct SlotOccupant {
+    pub datum: DatumHandle,
+    pub tag_path: String,
+    // First and last time the occupant was seen, objects already present when the session started spawn at its first snapshot.
+    pub spawned: Duration,
+    pub despawned: Option<Duration>,
+    pub last_position: [f32; 3],
+    // Flags the occupant was first seen with.
+    pub spawn_flags: u32
+}
+
+impl SlotOccupant {
+    pub fn is_alive_at(&self, timestamp: Duration) -> bool {
+        self.spawned <= timestamp && self.despawned.is_none_or(|despawned| timestamp < despawned)
+    }
+}
+
+pub struct SlotHistory {
+    slots: Vec<Vec<SlotOccupant>>
+}
+
+impl SlotHistory {
+    pub fn new() -> SlotHistory {
+        SlotHistory { slots: Vec::new() }
+    }
+
+    pub fn occupants(&self, index: usize) -> &[SlotOccupant] {
+        self.slots.get(index).map(|occupants| occupants.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn occupant_at(&self, index: usize, timestamp: Duration) -> Option<&SlotOccupant> {
+        self.occupants(index).iter().rev().find(|occupant| occupant.is_alive_at(timestamp))
+    }
+
+    pub fn record(&mut self, snapshot: &EngineSnapshot, timestamp: Duration) {
+        if self.slots.len() < snapshot.object_pool_entries.len() {
+            self.slots.resize_with(snapshot.object_pool_entries.len(), Vec::new);
+        }
+
+        for (index, occupants) in self.slots.iter_mut().enumerate() {
+            let current = match (snapshot.object_pool_entries.get(index), snapshot.game_object_entries.get(index)) {
+                (Some(Some(pool_entry)), Some(Some(game_object))) => Some((pool_entry, game_object)),
+                _ => None
+            };
+
+            let active = occupants.last_mut().filter(|occupant| occupant.despawned.is_none());
+
+            match (active, current) {
+                (Some(occupant), Some((pool_entry, game_object))) if occupant.datum.get_id() == pool_entry.id => {
+                    occupant.last_position = game_object.position;
+                }
+                (active, current) => {
+                    if let Some(occupant) = active {
+                        occupant.despawned = Some(timestamp);
+                    }
+
+                    if let Some((pool_entry, game_object)) = current {
+                        occupants.push(SlotOccupant {
+                            datum: DatumHandle::new_from_index_id(index as u16, pool_entry.id),
+                            tag_path: snapshot.tags.get(&game_object.tag_index).cloned().unwrap_or("UNKNOWN".to_string()),
+                            spawned: timestamp,
+                            despawned: None,
+                            last_position: game_object.position,
+                            spawn_flags: game_object.flags
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
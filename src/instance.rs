@@ -0,0 +1,481 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH}
+};
+
+use crate::{
+    alerts::{AlertLog, AlertRule},
+    diff::{diff_snapshots, DEFAULT_MOVE_THRESHOLD},
+    engine::{engine_signature, locate_object_tag, DatumHandle, EngineSnapshot, GameProfile, GAME_OBJECT_FLAGS_OFFSET},
+    freeze::FreezeList,
+    gc::GcLog,
+    history::SlotHistory,
+    journal::{ObjectWrite, WriteContext, WriteJournal},
+    memory::{find_guest_ram, find_processes, DirectMemory, GuestMemory, ProcessMemory},
+    playback::Playback,
+    recorder::{SessionRecorder, SESSION_EXTENSION},
+    sampler::{SampleSettings, Sampler}
+};
+
+// How long guest RAM may go without the engine signatures before the emulator is searched for it again.
+const SIGNATURE_RESCAN_DELAY: Duration = Duration::from_secs(3);
+
+// How often to look for guest RAM or a new emulator process while reattaching.
+const REATTACH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub enum LinkState {
+    Connected,
+    // The emulator is running but guest RAM no longer holds a valid snapshot, e.g. while the title reboots.
+    SignatureLost { since: Instant },
+    // The emulator exited, waiting for a new process to show up.
+    ProcessExited { since: Instant },
+    // The emulator could not be opened, e.g. it runs as another user, tried again like a restart.
+    OpenFailed { since: Instant, error: String }
+}
+
+// Guest RAM found by a discovery thread.
+struct Discovered {
+    pid: u32,
+    base: usize
+}
+
+// One attached emulator or opened recording, each gets its own tab in the viewer.
+pub struct Instance {
+    pub label: String,
+    pub process_name: String,
+    // Handle used for writes, guest RAM is read by the sampler thread.
+    pub memory: Option<ProcessMemory>,
+    // Open the write handle without write rights.
+    pub read_only: bool,
+    // Write rights were asked for and refused, not asked for again until read only is toggled.
+    pub write_rights_refused: bool,
+    pub sampler: Option<Sampler>,
+    pub sample_settings: SampleSettings,
+    // Bytes copied out of the emulator for the latest sample.
+    pub sample_bytes: usize,
+    // How long the emulator was suspended for the latest sample, and the longest pause so far.
+    pub last_pause: Option<Duration>,
+    pub longest_pause: Duration,
+    // Why the sampler has no game tick, shown in the menu bar.
+    pub calibration_error: Option<String>,
+    pub link: LinkState,
+    pub last_reattach: Option<Instant>,
+    // Listing processes and scanning their memory takes a while, so it runs on its own thread.
+    discovery: Option<Receiver<Option<Discovered>>>,
+    pub playback: Option<Playback>,
+    pub profile: GameProfile,
+    pub attached_at: Instant,
+    pub snapshot: Option<EngineSnapshot>,
+    pub gc_log: GcLog,
+    pub slot_history: SlotHistory,
+    pub recorder: Option<SessionRecorder>,
+    // Every write made through this instance, kept across reattaches.
+    pub journal: WriteJournal,
+    pub journal_status: Option<String>,
+    // Why the last write was refused and when.
+    pub write_error: Option<(Instant, String)>,
+    pub freezes: FreezeList,
+    pub alert_log: AlertLog,
+    pub target_index: u32,
+    pub history_index: i32
+}
+
+impl Instance {
+    fn new(label: String, profile: &GameProfile, sample_settings: SampleSettings) -> Instance {
+        Instance {
+            label: label,
+            process_name: String::default(),
+            memory: None,
+            read_only: true,
+            write_rights_refused: false,
+            sampler: None,
+            sample_settings: sample_settings,
+            sample_bytes: 0,
+            last_pause: None,
+            longest_pause: Duration::ZERO,
+            calibration_error: None,
+            link: LinkState::Connected,
+            last_reattach: None,
+            discovery: None,
+            playback: None,
+            profile: profile.clone(),
+            attached_at: Instant::now(),
+            snapshot: None,
+            gc_log: GcLog::new(),
+            slot_history: SlotHistory::new(),
+            recorder: None,
+            journal: WriteJournal::new(),
+            journal_status: None,
+            write_error: None,
+            freezes: FreezeList::new(),
+            alert_log: AlertLog::new(),
+            target_index: 0,
+            history_index: 0
+        }
+    }
+
+    pub fn attach(process_name: &str, virtual_address: usize, pid: u32, profile: &GameProfile, sample_settings: SampleSettings, read_only: bool) -> Instance {
+        let mut instance = Instance::new(String::default(), profile, sample_settings);
+        instance.process_name = process_name.to_string();
+        instance.read_only = read_only;
+        instance.label = format!("PID {}", pid);
+
+        if let Err(e) = instance.connect(virtual_address, pid) {
+            instance.open_failed(e);
+        }
+
+        instance
+    }
+
+    fn connect(&mut self, virtual_address: usize, pid: u32) -> Result<(), String> {
+        self.memory = Some(ProcessMemory::new(virtual_address, pid, self.read_only)?);
+        self.sampler = Some(Sampler::spawn(virtual_address, pid, &self.profile, self.sample_settings));
+        self.label = format!("PID {}", pid);
+        Ok(())
+    }
+
+    // Keep looking for the emulator, the error is only printed when it changes so retries do not flood the console.
+    fn open_failed(&mut self, error: String) {
+        let since = match &self.link {
+            LinkState::OpenFailed { since, error: previous } if *previous == error => *since,
+            _ => {
+                println!("{}", error);
+                Instant::now()
+            }
+        };
+
+        self.link = LinkState::OpenFailed { since: since, error: error };
+    }
+
+    pub fn open_playback(path: &Path, profile: &GameProfile) -> Result<Instance, String> {
+        let label = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or(path.display().to_string());
+        let mut instance = Instance::new(label, profile, SampleSettings { interval: Duration::ZERO, tick_sync: false, suspend: false });
+
+        match Playback::open(path, profile) {
+            Ok(playback) => instance.playback = Some(playback),
+            Err(e) => return Err(format!("Could not open {}: {}", path.display(), e))
+        }
+
+        Ok(instance)
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.memory.as_ref().map(|memory| memory.pid())
+    }
+
+    // Take in everything the sampler read since the last frame, or move the playback along.
+    // Processes owned by other instances are listed in `claimed_pids` so a restart is not picked up twice.
+    // Frozen values are written back after every sample unless the viewer is read only.
+    // Alert rules only run on live samples, scrubbing a recording would set them off out of order.
+    pub fn update(&mut self, sample_settings: SampleSettings, read_only: bool, alert_rules: &[AlertRule], elapsed: Duration, claimed_pids: &[u32]) {
+        if let Some(playback) = self.playback.as_mut() {
+            // The garbage collection log and slot history cover the whole recording, so they only show up once every frame was read.
+            if let Some(scan) = playback.poll_scan() {
+                self.gc_log = scan.gc_log;
+                self.slot_history = scan.slot_history;
+            }
+
+            playback.advance(elapsed);
+
+            if !playback.is_loaded() {
+                self.snapshot = playback.load_snapshot();
+            }
+
+            return;
+        }
+
+        if read_only != self.read_only {
+            if !self.write_rights_refused {
+                self.set_read_only(read_only);
+            }
+        } else {
+            self.write_rights_refused = false;
+        }
+
+        if sample_settings != self.sample_settings {
+            self.sample_settings = sample_settings;
+            if let Some(sampler) = &self.sampler {
+                sampler.set_settings(sample_settings);
+            }
+        }
+
+        self.reattach(claimed_pids);
+
+        let samples: Vec<_> = match &self.sampler {
+            Some(sampler) => sampler.samples().collect(),
+            None => return
+        };
+
+        for sample in samples {
+            // Wait for the emulator again, the same way as when it was first opened.
+            if let Some(error) = sample.error {
+                self.memory = None;
+                self.sampler = None;
+                self.open_failed(error);
+                return;
+            }
+
+            self.sample_bytes = sample.bytes_read;
+            self.last_pause = sample.pause;
+            self.longest_pause = self.longest_pause.max(sample.pause.unwrap_or_default());
+            self.calibration_error = sample.calibration_error;
+
+            match sample.snapshot {
+                Some(snapshot) => {
+                    // The stale snapshot would otherwise be diffed against a rebooted game.
+                    if !matches!(self.link, LinkState::Connected) {
+                        self.link = LinkState::Connected;
+                        self.snapshot = None;
+                    }
+
+                    let timestamp = sample.taken_at.saturating_duration_since(self.attached_at);
+                    self.ingest_snapshot(snapshot, timestamp, alert_rules);
+
+                    if !self.read_only {
+                        if let (Some(memory), Some(snapshot)) = (self.memory.as_mut(), &self.snapshot) {
+                            self.freezes.apply(memory, &self.profile, snapshot);
+                        }
+                    }
+                }
+                None => {
+                    if matches!(self.link, LinkState::Connected) {
+                        self.link = LinkState::SignatureLost { since: sample.taken_at };
+                    }
+                }
+            }
+        }
+    }
+
+    fn reattach(&mut self, claimed_pids: &[u32]) {
+        if let Some(memory) = &self.memory {
+            if !memory.is_alive() {
+                println!("Process {} exited, waiting for {} to start again.", memory.pid(), self.process_name);
+                self.memory = None;
+                self.sampler = None;
+                self.discovery = None;
+                self.link = LinkState::ProcessExited { since: Instant::now() };
+            }
+        }
+
+        if let Some(discovery) = &self.discovery {
+            match discovery.try_recv() {
+                Ok(discovered) => {
+                    self.discovery = None;
+                    if let Some(discovered) = discovered {
+                        self.discovered(discovered, claimed_pids);
+                    }
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => self.discovery = None
+            }
+        }
+
+        let due = match &self.link {
+            LinkState::Connected => false,
+            LinkState::SignatureLost { since } => since.elapsed() >= SIGNATURE_RESCAN_DELAY,
+            LinkState::ProcessExited { .. } | LinkState::OpenFailed { .. } => true
+        };
+
+        if !due || self.last_reattach.is_some_and(|last_reattach| last_reattach.elapsed() < REATTACH_INTERVAL) {
+            return;
+        }
+
+        self.last_reattach = Some(Instant::now());
+        let (offset, signature) = engine_signature(&self.profile);
+        let (sender, receiver) = mpsc::channel();
+        self.discovery = Some(receiver);
+
+        match &self.memory {
+            // Same process, guest RAM may have been mapped somewhere else.
+            Some(memory) => {
+                let pid = memory.pid();
+                thread::spawn(move || {
+                    let _ = sender.send(find_guest_ram(pid, offset, signature).map(|base| Discovered { pid: pid, base: base }));
+                });
+            }
+            None => {
+                let process_name = self.process_name.clone();
+                let claimed_pids = claimed_pids.to_vec();
+                thread::spawn(move || {
+                    let discovered = find_processes(&process_name).into_iter()
+                        .filter(|process| !claimed_pids.contains(&process.pid))
+                        .find_map(|process| find_guest_ram(process.pid, offset, signature).map(|base| Discovered { pid: process.pid, base: base }));
+                    let _ = sender.send(discovered);
+                });
+            }
+        }
+    }
+
+    fn discovered(&mut self, discovered: Discovered, claimed_pids: &[u32]) {
+        match self.memory.as_mut() {
+            Some(memory) => {
+                if memory.pid() == discovered.pid && memory.virtual_address() != discovered.base {
+                    println!("Found guest RAM of process {} at {:X}.", discovered.pid, discovered.base);
+                    memory.set_virtual_address(discovered.base);
+
+                    if let Some(sampler) = &self.sampler {
+                        sampler.set_virtual_address(discovered.base);
+                    }
+                }
+            }
+            // Another instance may have taken the process while the thread was looking.
+            None if claimed_pids.contains(&discovered.pid) => {}
+            None => {
+                match self.connect(discovered.base, discovered.pid) {
+                    Ok(()) => println!("Reattached to process {}, guest RAM at {:X}.", discovered.pid, discovered.base),
+                    Err(e) => self.open_failed(e)
+                }
+            }
+        }
+    }
+
+    // Shown while a live instance is not receiving snapshots.
+    pub fn link_status(&self) -> Option<String> {
+        match &self.link {
+            LinkState::Connected => None,
+            LinkState::SignatureLost { since } => Some(format!("No valid snapshot for {}s, waiting for the game", since.elapsed().as_secs())),
+            LinkState::ProcessExited { since } => Some(format!("{} exited {}s ago, waiting for it to restart", self.process_name, since.elapsed().as_secs())),
+            LinkState::OpenFailed { since, error } => Some(format!("{}, retrying for {}s", error, since.elapsed().as_secs()))
+        }
+    }
+
+    // Feed a freshly read snapshot through the event log, alert rules and recorder, then make it the current snapshot.
+    fn ingest_snapshot(&mut self, snapshot: EngineSnapshot, timestamp: Duration, alert_rules: &[AlertRule]) {
+        let mut alert_writes = Vec::new();
+        if let Some(previous) = &self.snapshot {
+            let events = diff_snapshots(previous, &snapshot, DEFAULT_MOVE_THRESHOLD);
+            self.gc_log.process(previous, &snapshot, &events, timestamp);
+            alert_writes = self.alert_log.process(alert_rules, previous, &snapshot, timestamp);
+        }
+
+        // Alert writes are journaled like any other edit so they can be undone.
+        for (rule, write) in alert_writes {
+            let result = match self.memory.as_mut() {
+                Some(_) if self.read_only => Err(format!("Alert {} did not write, the viewer is read only.", rule)),
+                Some(memory) => self.journal.write(memory, write.address, &write.bytes, WriteContext {
+                    description: format!("Alert {}", rule),
+                    object: None,
+                    timestamp: timestamp,
+                    tick: snapshot.game_tick
+                }),
+                None => Err("Not attached to a process.".to_string())
+            };
+            self.report_write(result);
+        }
+
+        self.slot_history.record(&snapshot, timestamp);
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&snapshot) {
+                println!("Could not write to session file {}: {}", recorder.path().display(), e);
+                self.recorder = None;
+            }
+        }
+
+        self.snapshot = Some(snapshot);
+    }
+
+    pub fn start_recording(&mut self) {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let path = match self.pid() {
+            Some(pid) => PathBuf::from(format!("session-{}-{}.{}", pid, seconds, SESSION_EXTENSION)),
+            None => PathBuf::from(format!("session-{}.{}", seconds, SESSION_EXTENSION))
+        };
+
+        match SessionRecorder::create(&path) {
+            Ok(recorder) => self.recorder = Some(recorder),
+            Err(e) => println!("Could not create session file {}: {}", path.display(), e)
+        }
+    }
+
+    // Write to a field of an object through the journal so the edit can be undone.
+    // The object is looked up again first, the write is refused if its slot was reused since the snapshot on screen.
+    pub fn write_object(&mut self, datum: &DatumHandle, offset: usize, bytes: &[u8], description: String, tick: Option<u32>) -> Result<(), String> {
+        let timestamp = self.session_timestamp();
+        let memory = self.memory.as_mut().ok_or("Not attached to a process.")?;
+
+        let (address, tag_index) = locate_object_tag(&mut DirectMemory(memory), &self.profile, datum)?;
+        let object = ObjectWrite { datum: datum.clone(), tag_index: Some(tag_index), offset: offset };
+        let context = WriteContext { description: description, object: Some(object), timestamp: timestamp, tick: tick };
+        self.journal.write(memory, address + offset, bytes, context)
+    }
+
+    // Set or clear one bit of an object's flags, read from the live object right before writing so bits the game changed since the last sample are kept.
+    // Returns the flags that were written.
+    pub fn write_object_flag(&mut self, datum: &DatumHandle, bit: u32, set: bool, description: String, tick: Option<u32>) -> Result<u32, String> {
+        let timestamp = self.session_timestamp();
+        let memory = self.memory.as_mut().ok_or("Not attached to a process.")?;
+
+        let (address, tag_index) = locate_object_tag(&mut DirectMemory(memory), &self.profile, datum)?;
+        let address = address + GAME_OBJECT_FLAGS_OFFSET;
+        let mut live = [0u8; 4];
+        if !DirectMemory(memory).read_into(address, &mut live) {
+            return Err(format!("Could not read the flags of {}.", datum.get_handle()));
+        }
+
+        let flags = u32::from_le_bytes(live);
+        let flags = if set { flags | (1 << bit) } else { flags & !(1 << bit) };
+
+        let object = ObjectWrite { datum: datum.clone(), tag_index: Some(tag_index), offset: GAME_OBJECT_FLAGS_OFFSET };
+        let context = WriteContext { description: description, object: Some(object), timestamp: timestamp, tick: tick };
+        self.journal.write(memory, address, &flags.to_le_bytes(), context)?;
+        Ok(flags)
+    }
+
+    pub fn undo(&mut self) -> Result<(), String> {
+        let memory = self.memory.as_mut().ok_or("Not attached to a process.")?;
+        self.journal.undo(memory, &self.profile)
+    }
+
+    pub fn redo(&mut self) -> Result<(), String> {
+        let memory = self.memory.as_mut().ok_or("Not attached to a process.")?;
+        self.journal.redo(memory, &self.profile)
+    }
+
+    pub fn replay_journal(&mut self, path: &Path, tick: Option<u32>) -> Result<usize, String> {
+        let timestamp = self.session_timestamp();
+        let memory = self.memory.as_mut().ok_or("Not attached to a process.")?;
+        self.journal.replay(path, memory, &self.profile, timestamp, tick)
+    }
+
+    // Remember why the last write was refused so it can be shown next to the menu bar.
+    pub fn report_write(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            println!("{}", e);
+            self.write_error = Some((Instant::now(), e));
+        }
+    }
+
+    // Reopen the write handle with or without write rights, the old handle is kept if the emulator refuses.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        let reopened = match &self.memory {
+            Some(memory) if memory.is_read_only() != read_only => ProcessMemory::new(memory.virtual_address(), memory.pid(), read_only),
+            _ => {
+                self.read_only = read_only;
+                return;
+            }
+        };
+
+        match reopened {
+            Ok(memory) => {
+                self.memory = Some(memory);
+                self.read_only = read_only;
+            }
+            Err(e) => {
+                self.write_rights_refused = true;
+                let mode = if self.read_only { "read only" } else { "writable" };
+                self.report_write(Err(format!("{}, staying {}.", e, mode)));
+            }
+        }
+    }
+
+    // Time of the snapshot on screen, relative to the start of the session.
+    pub fn session_timestamp(&self) -> Duration {
+        match &self.playback {
+            Some(playback) => playback.timestamp(),
+            None => self.attached_at.elapsed()
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{locate_object, locate_slot_object, DatumHandle, GameProfile},
+    memory::{DirectMemory, GuestMemory, ProcessMemory},
+    serialization::hex_vec
+};
+
+// Bump whenever the exported journal changes shape.
+pub const JOURNAL_FORMAT_VERSION: u32 = 1;
+
+// Writes to the same address this close together are merged, so dragging a value is one entry.
+const COALESCE_WINDOW: Duration = Duration::from_secs(1);
+
+// The object a write was aimed at, so undo, redo and replay find it again instead of trusting the address.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObjectWrite {
+    pub datum: DatumHandle,
+    // Lets a replay find the object again after a restart, when its salt is different. Missing from older journals.
+    #[serde(default)]
+    pub tag_index: Option<u32>,
+    pub offset: usize
+}
+
+// Everything recorded with a write besides the bytes.
+pub struct WriteContext {
+    pub description: String,
+    pub object: Option<ObjectWrite>,
+    // Time since the session started and the game tick of the snapshot on screen.
+    pub timestamp: Duration,
+    pub tick: Option<u32>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    // Time since the session started and the game tick of the snapshot on screen.
+    pub timestamp_ms: u64,
+    pub tick: Option<u32>,
+    // Physical guest address.
+    pub address: usize,
+    #[serde(with = "hex_vec")]
+    pub old_bytes: Vec<u8>,
+    #[serde(with = "hex_vec")]
+    pub new_bytes: Vec<u8>,
+    pub description: String,
+    #[serde(default)]
+    pub object: Option<ObjectWrite>
+}
+
+impl JournalEntry {
+    // Where the entry has to be written now, failing if its object is gone.
+    fn resolve(&self, memory: &ProcessMemory, profile: &GameProfile) -> Result<usize, String> {
+        match &self.object {
+            Some(object) => Ok(locate_object(&mut DirectMemory(memory), profile, &object.datum)? + object.offset),
+            None => Ok(self.address)
+        }
+    }
+
+    // Where a replayed entry has to be written and the object it now lands on.
+    // Salts start over when the game restarts, so objects are found by slot index and tag instead.
+    fn resolve_replay(&self, memory: &ProcessMemory, profile: &GameProfile) -> Result<(usize, Option<ObjectWrite>), String> {
+        match &self.object {
+            Some(ObjectWrite { datum, tag_index: Some(tag_index), offset }) => {
+                let (datum, address) = locate_slot_object(&mut DirectMemory(memory), profile, datum.get_index(), *tag_index)?;
+                let object = ObjectWrite { datum: datum, tag_index: Some(*tag_index), offset: *offset };
+                Ok((address + offset, Some(object)))
+            }
+            _ => Ok((self.resolve(memory, profile)?, self.object.clone()))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalDocument {
+    version: u32,
+    entries: Vec<JournalEntry>
+}
+
+// Every write made to guest memory, with the bytes it replaced so it can be undone.
+pub struct WriteJournal {
+    entries: Vec<JournalEntry>,
+    // Entries before this one are applied, the rest have been undone and can be redone.
+    position: usize,
+    last_write: Option<Instant>
+}
+
+impl WriteJournal {
+    pub fn new() -> WriteJournal {
+        WriteJournal {
+            entries: Vec::new(),
+            position: 0,
+            last_write: None
+        }
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.entries.len()
+    }
+
+    // Write `bytes` to guest memory and record what they replaced.
+    pub fn write(&mut self, memory: &mut ProcessMemory, address: usize, bytes: &[u8], context: WriteContext) -> Result<(), String> {
+        let mut old_bytes = vec![0u8; bytes.len()];
+        if !DirectMemory(memory).read_into(address, &mut old_bytes) {
+            return Err(format!("Could not read {} bytes at {:#X}.", bytes.len(), address));
+        }
+
+        if !memory.write(address, bytes) {
+            return Err(format!("Could not write {} bytes at {:#X}.", bytes.len(), address));
+        }
+
+        // A new write discards whatever was undone.
+        self.entries.truncate(self.position);
+
+        let coalesce = self.last_write.is_some_and(|last_write| last_write.elapsed() < COALESCE_WINDOW);
+        match self.entries.last_mut() {
+            Some(last) if coalesce && last.address == address && last.new_bytes.len() == bytes.len() && last.description == context.description => {
+                last.new_bytes = bytes.to_vec();
+                last.timestamp_ms = context.timestamp.as_millis() as u64;
+                last.tick = context.tick;
+            }
+            _ => {
+                self.entries.push(JournalEntry {
+                    timestamp_ms: context.timestamp.as_millis() as u64,
+                    tick: context.tick,
+                    address: address,
+                    old_bytes: old_bytes,
+                    new_bytes: bytes.to_vec(),
+                    description: context.description,
+                    object: context.object
+                });
+            }
+        }
+
+        self.position = self.entries.len();
+        self.last_write = Some(Instant::now());
+
+        Ok(())
+    }
+
+    pub fn undo(&mut self, memory: &mut ProcessMemory, profile: &GameProfile) -> Result<(), String> {
+        if !self.can_undo() {
+            return Err("Nothing to undo.".to_string());
+        }
+
+        let entry = &self.entries[self.position - 1];
+        let address = entry.resolve(memory, profile).map_err(|e| format!("Could not undo \"{}\": {}", entry.description, e))?;
+        if !memory.write(address, &entry.old_bytes) {
+            return Err(format!("Could not undo \"{}\".", entry.description));
+        }
+
+        self.position -= 1;
+        self.last_write = None;
+        Ok(())
+    }
+
+    pub fn redo(&mut self, memory: &mut ProcessMemory, profile: &GameProfile) -> Result<(), String> {
+        if !self.can_redo() {
+            return Err("Nothing to redo.".to_string());
+        }
+
+        let entry = &self.entries[self.position];
+        let address = entry.resolve(memory, profile).map_err(|e| format!("Could not redo \"{}\": {}", entry.description, e))?;
+        if !memory.write(address, &entry.new_bytes) {
+            return Err(format!("Could not redo \"{}\".", entry.description));
+        }
+
+        self.position += 1;
+        self.last_write = None;
+        Ok(())
+    }
+
+    // Save the applied entries, undone ones are left out.
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let document = JournalDocument {
+            version: JOURNAL_FORMAT_VERSION,
+            entries: self.entries[..self.position].to_vec()
+        };
+
+        let json = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+
+    // Apply every write of an exported journal in order, each one is recorded so the replay can be undone.
+    // Object writes land on whatever object of the same tag holds the slot now, so a journal still applies after a restart
+    // as long as the objects were created in the same order. The replay stops at the first slot holding something else.
+    pub fn replay(&mut self, path: &Path, memory: &mut ProcessMemory, profile: &GameProfile, timestamp: Duration, tick: Option<u32>) -> Result<usize, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let document: JournalDocument = serde_json::from_str(&json).map_err(|e| format!("Could not parse {}: {}", path.display(), e))?;
+
+        if document.version != JOURNAL_FORMAT_VERSION {
+            return Err(format!("{} uses journal format {}, expected {}.", path.display(), document.version, JOURNAL_FORMAT_VERSION));
+        }
+
+        for (index, entry) in document.entries.iter().enumerate() {
+            // Replayed entries are never merged with each other.
+            self.last_write = None;
+
+            let result = entry.resolve_replay(memory, profile).and_then(|(address, object)| {
+                let context = WriteContext { description: entry.description.clone(), object: object, timestamp: timestamp, tick: tick };
+                self.write(memory, address, &entry.new_bytes, context)
+            });
+
+            if let Err(e) = result {
+                return Err(format!("Write {} of {} failed: {}", index + 1, document.entries.len(), e));
+            }
+        }
+
+        self.last_write = None;
+        Ok(document.entries.len())
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use imgui::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{load_json, save_json};
+
+pub const LAYOUT_PRESETS_FILE: &str = "layouts.json";
+
+// Window positions, sizes and docking saved under a name, in imgui's ini format.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub ini: String
+}
+
+// Layouts can only be read or changed between frames, so the menu asks for it and the main loop does it.
+pub enum LayoutRequest {
+    Save(String),
+    Load(String),
+    Delete(String)
+}
+
+pub fn load_layout_presets(path: &Path) -> Vec<LayoutPreset> {
+    load_json(path).unwrap_or_else(|e| {
+        println!("{}", e);
+        Vec::new()
+    })
+}
+
+pub fn save_layout_presets(path: &Path, presets: &[LayoutPreset]) -> Result<(), String> {
+    save_json(path, presets)
+}
+
+// Saving under an existing name replaces that preset. Returns whether the presets file needs to be written again.
+pub fn handle_layout_request(imgui: &mut Context, presets: &mut Vec<LayoutPreset>, request: LayoutRequest) -> Result<bool, String> {
+    match request {
+        LayoutRequest::Save(name) => {
+            let mut ini = String::new();
+            imgui.save_ini_settings(&mut ini);
+
+            match presets.iter_mut().find(|preset| preset.name == name) {
+                Some(preset) => preset.ini = ini,
+                None => presets.push(LayoutPreset { name: name, ini: ini })
+            }
+            Ok(true)
+        }
+        LayoutRequest::Load(name) => {
+            let preset = presets.iter().find(|preset| preset.name == name).ok_or(format!("There is no layout named {}.", name))?;
+            imgui.load_ini_settings(&preset.ini);
+            Ok(false)
+        }
+        LayoutRequest::Delete(name) => {
+            presets.retain(|preset| preset.name != name);
+            Ok(true)
+        }
+    }
+}
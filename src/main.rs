@@ -1,31 +1,183 @@
 #![allow(dead_code)]
+mod alerts;
+mod cli;
+mod config;
+mod diff;
 mod engine;
+mod filter;
+mod flags;
+mod freeze;
+mod gc;
+mod headless;
+mod history;
+mod instance;
+mod journal;
+mod layout;
 mod memory;
-
-use std::ffi::OsStr;
-
-use engine::{build_snapshot, DatumHandle, EngineSnapshot};
+mod playback;
+mod recorder;
+mod rows;
+mod sampler;
+mod serialization;
+mod watch;
+
+use std::{cmp::Ordering, path::{Path, PathBuf}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use alerts::{load_alert_rules, save_alert_rules, AlertActions, AlertCondition, AlertRule, AlertWrite, ALERT_CONDITIONS, ALERT_RULES_FILE};
+use cli::Options;
+use config::{config_path, load_config, save_config, Config, Theme, THEMES};
+use engine::{DatumHandle, EngineSnapshot, GameProfile, GAME_OBJECT_FLAGS_OFFSET, GAME_OBJECT_POSITION_OFFSET, GAME_PROFILES};
+use filter::{distance, load_filter_presets, player_position, save_filter_presets, FilterPreset, ObjectFilter, ObjectMatcher, RegexCache, FILTER_PRESETS_FILE, SLOT_FILTERS};
+use flags::{is_flag_set, object_flag_name, set_flag_names, OBJECT_FLAG_BITS};
+use freeze::{parse_hex_bytes, FreezeField, FreezeTarget};
 use glow::HasContext;
-use imgui::{Condition, Context, TableBgTarget, TableFlags, Ui};
+use imgui::{Condition, ConfigFlags, Context, Key, ListClipper, MouseButton, StyleColor, TabItemFlags, TableBgTarget, TableColumnFlags, TableColumnSetup, TableFlags, TableSortDirection, TreeNodeFlags, Ui, WindowFlags};
 use imgui_glow_renderer::{glow, AutoRenderer};
 use imgui_sdl2_support::SdlPlatform;
-use memory::ProcessMemory;
+use instance::Instance;
+use layout::{handle_layout_request, load_layout_presets, save_layout_presets, LayoutPreset, LayoutRequest, LAYOUT_PRESETS_FILE};
+use memory::{find_processes, find_single_process, ProcessInfo};
+use playback::Playback;
+use rows::{object_row, ObjectRow};
+use sampler::{sample_interval, SampleSettings};
+use serialization::{save_snapshot, SNAPSHOT_EXTENSION};
+use watch::{load_watchlists, save_watchlists, Watch, WatchTarget, Watchlists, PLAYER_FIELDS, UNKNOWN_MAP, VALUE_KINDS, WATCHLISTS_FILE};
 use sdl2::{
     event::Event,
     video::{GLProfile, Window},
 };
-use sysinfo::System;
 
 static GREEN: [f32; 4] = [0.69, 0.87, 0.15, 1.0];
 static RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
 static ORANGE: [f32; 4] = [0.97, 0.7, 0.17, 1.0];
 static DARK_GREY: [f32; 4] = [0.14, 0.14, 0.14, 1.0];
 static WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+static GREY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+static TIMELINE_HEIGHT: f32 = 90.0;
+
+// Window docking, sizes and positions and the column layout of the tables, kept next to the config file.
+static LAYOUT_FILE: &str = "layout.ini";
+
+// Width of the Players Globals, Inspector, Watchlist, Alerts and Logs column the first time the viewer is opened.
+static SIDE_PANEL_WIDTH: f32 = 450.0;
+
+// How long a refused write stays in the menu bar.
+static WRITE_ERROR_DURATION: Duration = Duration::from_secs(5);
+
+// How long a watched value stays highlighted after it changes.
+static WATCH_CHANGE_DURATION: Duration = Duration::from_secs(2);
+
+// How long an alert toast stays on screen.
+static TOAST_DURATION: Duration = Duration::from_secs(5);
 
 struct DrawContext {
-    memory: Option<ProcessMemory>,
+    instances: Vec<Instance>,
+    active_instance: usize,
+    // Instance whose tab should be brought to the front on the next frame.
+    select_instance: Option<usize>,
+    show_attach: bool,
+    settings: Settings
+}
+
+// Viewer wide options shared by every instance.
+struct Settings {
+    process_name: String,
     virtual_address: String,
-    target_index: u32
+    selected_pid: Option<u32>,
+    playback_path: String,
+    playback_error: Option<String>,
+    journal_path: String,
+    // Fields of the Frozen Values form.
+    freeze_kind: usize,
+    freeze_offset: String,
+    freeze_value: String,
+    freeze_error: Option<String>,
+    watchlists: Watchlists,
+    // Fields of the Watchlist form.
+    watch_kind: usize,
+    watch_label: String,
+    watch_player: i32,
+    watch_player_field: usize,
+    watch_address: String,
+    watch_value_kind: usize,
+    watch_error: Option<String>,
+    alert_rules: Vec<AlertRule>,
+    // Fields of the Alerts form.
+    alert_name: String,
+    alert_condition: usize,
+    alert_slot: i32,
+    alert_tag_path: String,
+    alert_value: i32,
+    alert_actions: AlertActions,
+    alert_write_address: String,
+    alert_write_bytes: String,
+    alert_error: Option<String>,
+    object_filter: ObjectFilter,
+    filter_presets: Vec<FilterPreset>,
+    filter_preset_name: String,
+    filter_regex: RegexCache,
+    // Column the Objects table is sorted by and whether it is descending.
+    object_sort: Option<(ObjectColumn, bool)>,
+    // Slot picked with Set, given to every new instance.
+    target_index: u32,
+    theme: Theme,
+    // Panels that can be closed, the Objects window always stays.
+    show_players: bool,
+    show_inspector: bool,
+    show_watchlist: bool,
+    show_alerts: bool,
+    show_logs: bool,
+    layout_presets: Vec<LayoutPreset>,
+    layout_name: String,
+    layout_request: Option<LayoutRequest>,
+    layout_error: Option<String>,
+    profile: GameProfile,
+    read_only: bool,
+    sample_rate: f32,
+    tick_sync: bool,
+    suspend_while_reading: bool
+}
+
+impl Settings {
+    fn config(&self, window_size: [u32; 2], window_position: [i32; 2]) -> Config {
+        Config {
+            process_name: self.process_name.clone(),
+            virtual_address: self.virtual_address.clone(),
+            profile: self.profile.name.to_string(),
+            read_only: self.read_only,
+            sample_rate: self.sample_rate,
+            tick_sync: self.tick_sync,
+            suspend_while_reading: self.suspend_while_reading,
+            target_index: self.target_index,
+            journal_path: self.journal_path.clone(),
+            theme: self.theme,
+            show_players: self.show_players,
+            show_inspector: self.show_inspector,
+            show_watchlist: self.show_watchlist,
+            show_alerts: self.show_alerts,
+            show_logs: self.show_logs,
+            window_size: window_size,
+            window_position: Some(window_position)
+        }
+    }
+
+    fn sample_settings(&self) -> SampleSettings {
+        SampleSettings {
+            interval: sample_interval(self.sample_rate),
+            tick_sync: self.tick_sync,
+            suspend: self.suspend_while_reading
+        }
+    }
+}
+
+// Tab bar state for one frame, applied once the active instance is no longer borrowed.
+struct InstanceTabs {
+    labels: Vec<String>,
+    active: usize,
+    select: Option<usize>,
+    close: Option<usize>,
+    attach_another: bool
 }
 
 // Create a new glow context.
@@ -35,6 +187,28 @@ fn glow_context(window: &Window) -> glow::Context {
     }
 }
 
+fn format_timestamp(timestamp: Duration) -> String {
+    let seconds = timestamp.as_secs();
+    format!("{:02}:{:02}.{:03}", seconds / 60, seconds % 60, timestamp.subsec_millis())
+}
+
+// Wall clock time of an event, followed by the game tick when it is known.
+fn format_event_time(timestamp: Duration, tick: Option<u32>) -> String {
+    match tick {
+        Some(tick) => format!("{} tick {}", format_timestamp(timestamp), tick),
+        None => format_timestamp(timestamp)
+    }
+}
+
+fn save_current_snapshot(snapshot: &EngineSnapshot) {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let path = PathBuf::from(format!("snapshot-{}.{}", seconds, SNAPSHOT_EXTENSION));
+
+    if let Err(e) = save_snapshot(&path, snapshot) {
+        println!("{}", e);
+    }
+}
+
 fn print_player(ui: &Ui, snapshot: &EngineSnapshot, player_index: u16) {
     if let Some(entry) = snapshot.player_pool_entries[player_index as usize].as_ref() {
         let local_dead_player = &snapshot.player_globals.local_dead_players[player_index as usize];
@@ -43,61 +217,1204 @@ fn print_player(ui: &Ui, snapshot: &EngineSnapshot, player_index: u16) {
         ui.text_colored(ORANGE, format!("Next Object Datum: {:#?}", local_dead_player));
         ui.text_colored(ORANGE, format!("Last Object Datum: {:#?}", entry.last_slave_unit_index));
 
-        {
-            let index = entry.slave_unit_index.get_index() as usize;
-    
-            let mut found = false;
-            if snapshot.object_pool_entries.get(index).is_some() && 
-               snapshot.game_object_entries.get(index).is_some() && 
-               snapshot.object_pool_entries[index].as_ref().is_some() && 
-               snapshot.game_object_entries[index].as_ref().is_some() 
-            {
-                let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+        {
+            let index = entry.slave_unit_index.get_index() as usize;
+    
+            let mut found = false;
+            if snapshot.object_pool_entries.get(index).is_some() && 
+               snapshot.game_object_entries.get(index).is_some() && 
+               snapshot.object_pool_entries[index].as_ref().is_some() && 
+               snapshot.game_object_entries[index].as_ref().is_some() 
+            {
+                let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+
+                ui.text_colored(ORANGE, format!("Position: X: {:.4} Y: {:.4} Z: {:.4}", game_object_entry.position[0], game_object_entry.position[1], game_object_entry.position[2]));
+                found = true;
+            }
+    
+            if !found {
+                ui.text_colored(ORANGE, format!("Position: None"));
+            }
+        }
+    } else { 
+        ui.text_colored(ORANGE, format!("Unit Handle: None"));
+        ui.text_colored(ORANGE, format!("Position: None"));
+    }
+
+    let handle = &snapshot.player_globals.local_dead_players[player_index as usize];
+    {
+        let index = handle.get_index() as usize;
+
+        let mut found = false;
+        if snapshot.object_pool_entries.get(index).is_some() && 
+           snapshot.game_object_entries.get(index).is_some() && 
+           snapshot.object_pool_entries[index].as_ref().is_some() && 
+           snapshot.game_object_entries[index].as_ref().is_some() 
+        {
+            let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+
+            ui.text_colored(ORANGE, format!("Next Datum Position: X: {:.4} Y: {:.4} Z: {:.4}", game_object_entry.position[0], game_object_entry.position[1], game_object_entry.position[2]));
+            found = true;
+        }
+
+        if !found {
+            ui.text_colored(ORANGE, format!("Next Datum Position: None"));
+        }
+    }
+
+    if let Some(entry) = snapshot.player_pool_entries[player_index as usize].as_ref() {
+    } else { 
+        ui.text_colored(ORANGE, format!("Last Unit Handle: None")) 
+    }
+}
+
+fn draw_timeline(ui: &Ui, playback: &mut Playback) {
+    if ui.button(if playback.playing { "Pause" } else { "Play" }) {
+        if !playback.playing && playback.position + 1 == playback.frames.len() {
+            playback.seek(0);
+        }
+        playback.playing = !playback.playing;
+    }
+
+    ui.same_line();
+    if ui.button("<") {
+        playback.step(-1);
+    }
+
+    ui.same_line();
+    if ui.button(">") {
+        playback.step(1);
+    }
+
+    ui.same_line();
+    if ui.button("<< Marker") {
+        if let Some(frame) = playback.previous_marker() {
+            playback.seek(frame);
+        }
+    }
+
+    ui.same_line();
+    if ui.button("Marker >>") {
+        if let Some(frame) = playback.next_marker() {
+            playback.seek(frame);
+        }
+    }
+
+    ui.same_line();
+    ui.text(format!(
+        "{} / {} | Snapshot {} / {} | {}",
+        format_timestamp(playback.timestamp()),
+        format_timestamp(playback.duration()),
+        playback.position + 1,
+        playback.frames.len(),
+        playback.frame_name(playback.position)
+    ));
+
+    if let Some((files, file_count)) = playback.scan_progress {
+        ui.same_line();
+        ui.text_colored(ORANGE, format!("| Reading {} / {} files", files, file_count));
+    }
+
+    if let Some(error) = &playback.scan_error {
+        ui.text_colored(RED, format!("Stopped reading at snapshot {}: {}", playback.frames.len(), error));
+    }
+
+    let last_frame = playback.frames.len() - 1;
+    let mut position = playback.position;
+
+    ui.set_next_item_width(-1.0);
+    if ui.slider_config("##Timeline", 0, last_frame).display_format("").build(&mut position) {
+        playback.seek(position);
+    }
+
+    // Event markers are drawn as ticks across the slider.
+    let min = ui.item_rect_min();
+    let max = ui.item_rect_max();
+    let draw_list = ui.get_window_draw_list();
+    let mut hovered_labels = Vec::new();
+
+    for marker in &playback.markers {
+        let x = min[0] + (max[0] - min[0]) * (marker.frame as f32 / last_frame.max(1) as f32);
+        draw_list.add_line([x, min[1]], [x, max[1]], RED).thickness(2.0).build();
+
+        let mouse = ui.io().mouse_pos;
+        if (mouse[0] - x).abs() <= 3.0 && mouse[1] >= min[1] && mouse[1] <= max[1] {
+            hovered_labels.push(format!("{}: {}", format_timestamp(playback.frames[marker.frame].timestamp), marker.label));
+        }
+    }
+
+    if !hovered_labels.is_empty() {
+        ui.tooltip_text(hovered_labels.join("\n"));
+    }
+
+    let preview = playback.markers.iter()
+        .rev()
+        .find(|marker| marker.frame <= playback.position)
+        .map(|marker| marker.label.clone())
+        .unwrap_or(format!("{} markers", playback.markers.len()));
+
+    ui.set_next_item_width(-1.0);
+    if let Some(combo) = ui.begin_combo("##Markers", preview) {
+        let mut selected = None;
+        for (marker_index, marker) in playback.markers.iter().enumerate() {
+            let label = format!("{} {}##marker{}", format_timestamp(playback.frames[marker.frame].timestamp), marker.label, marker_index);
+            if ui.selectable(label) {
+                selected = Some(marker.frame);
+            }
+        }
+
+        if let Some(frame) = selected {
+            playback.seek(frame);
+        }
+
+        combo.end();
+    }
+}
+
+fn draw_slot_history(ui: &Ui, instance: &mut Instance) {
+    ui.set_next_item_width(120.0);
+    ui.input_int("Slot", &mut instance.history_index).build();
+    instance.history_index = instance.history_index.max(0);
+
+    ui.same_line();
+    if ui.button("Use Target") {
+        instance.history_index = instance.target_index as i32;
+    }
+
+    let index = instance.history_index as usize;
+    let timestamp = instance.session_timestamp();
+    let occupants = instance.slot_history.occupants(index);
+
+    if occupants.is_empty() {
+        ui.text("No occupants recorded for this slot.");
+        return;
+    }
+
+    if let Some(table) = ui.begin_table_with_flags("SlotHistoryTable", 5, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H) {
+        ui.table_setup_column("ID");
+        ui.table_setup_column("Tag Name");
+        ui.table_setup_column("Spawned");
+        ui.table_setup_column("Despawned");
+        ui.table_setup_column("Last Position");
+        ui.table_headers_row();
+
+        for occupant in occupants.iter().rev() {
+            ui.table_next_row();
+
+            // Highlight whatever occupied the slot at the time being viewed.
+            if occupant.is_alive_at(timestamp) {
+                ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
+            }
+
+            ui.table_set_column_index(0);
+            ui.text_colored(GREEN, format!("{}", occupant.datum.get_id()));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(format!("Datum: {}", occupant.datum.get_handle()));
+            }
+
+            ui.table_next_column();
+            ui.text(&occupant.tag_path);
+
+            ui.table_next_column();
+            ui.text(format_timestamp(occupant.spawned));
+
+            ui.table_next_column();
+            match occupant.despawned {
+                Some(despawned) => ui.text_colored(RED, format_timestamp(despawned)),
+                None => ui.text("")
+            }
+
+            ui.table_next_column();
+            ui.text(format!("X: {:.4} Y: {:.4} Z: {:.4}", occupant.last_position[0], occupant.last_position[1], occupant.last_position[2]));
+        }
+
+        table.end();
+    }
+}
+
+// Search box and filters above the Objects table, None when nothing is filtered or the filter is invalid.
+fn draw_object_filter(ui: &Ui, settings: &mut Settings, snapshot: &EngineSnapshot) -> Option<ObjectMatcher> {
+    let filter = &mut settings.object_filter;
+
+    ui.set_next_item_width(300.0);
+    ui.input_text("Search", &mut filter.search).hint("Datum, index, ID, tag or class").build();
+    ui.same_line();
+    if ui.button("Clear Filters") {
+        *filter = ObjectFilter::default();
+    }
+
+    if ui.collapsing_header("Filters", TreeNodeFlags::empty()) {
+        ui.set_next_item_width(100.0);
+        ui.input_text("Tag Class", &mut filter.tag_class).hint("bipd").build();
+        ui.same_line();
+        ui.set_next_item_width(300.0);
+        ui.input_text("Tag Path", &mut filter.tag_path).build();
+        ui.same_line();
+        ui.checkbox("Regex", &mut filter.tag_path_regex);
+
+        ui.set_next_item_width(100.0);
+        if let Some(combo) = ui.begin_combo("Slots", SLOT_FILTERS[filter.slots]) {
+            for (slots, name) in SLOT_FILTERS.iter().enumerate() {
+                if ui.selectable_config(name).selected(slots == filter.slots).build() {
+                    filter.slots = slots;
+                }
+            }
+            combo.end();
+        }
+        ui.same_line();
+        ui.checkbox("Players Only", &mut filter.players_only);
+
+        ui.set_next_item_width(200.0);
+        let mut index_range = [filter.first_index, filter.last_index];
+        if ui.input_int2("Index Range", &mut index_range).build() {
+            filter.first_index = index_range[0];
+            filter.last_index = index_range[1];
+        }
+
+        let mut near_player = filter.distance_player >= 0;
+        if ui.checkbox("Near Player", &mut near_player) {
+            filter.distance_player = if near_player { 0 } else { -1 };
+        }
+        if near_player {
+            ui.same_line();
+            ui.set_next_item_width(80.0);
+            ui.input_int("##DistancePlayer", &mut filter.distance_player).build();
+            filter.distance_player = filter.distance_player.clamp(0, snapshot.player_globals.local_players.len() as i32 - 1);
+            ui.same_line();
+            ui.set_next_item_width(150.0);
+            ui.slider_config("Max Distance", 0.0, 100.0).display_format("%.1f").build(&mut filter.max_distance);
+        }
+
+        let preview = if settings.filter_presets.is_empty() { "No presets" } else { "Load preset" };
+        ui.set_next_item_width(200.0);
+        let mut load = None;
+        if let Some(combo) = ui.begin_combo("##FilterPresets", preview) {
+            for (index, preset) in settings.filter_presets.iter().enumerate() {
+                if ui.selectable(&preset.name) {
+                    load = Some(index);
+                }
+            }
+            combo.end();
+        }
+
+        if let Some(index) = load {
+            settings.object_filter = settings.filter_presets[index].filter.clone();
+            settings.filter_preset_name = settings.filter_presets[index].name.clone();
+        }
+
+        ui.same_line();
+        ui.set_next_item_width(200.0);
+        ui.input_text("##FilterPresetName", &mut settings.filter_preset_name).hint("Preset name").build();
+
+        // Saving under an existing name replaces that preset.
+        let existing = settings.filter_presets.iter().position(|preset| preset.name == settings.filter_preset_name);
+        let mut changed = false;
+
+        ui.same_line();
+        ui.disabled(settings.filter_preset_name.is_empty(), || {
+            if ui.button("Save Preset") {
+                let preset = FilterPreset { name: settings.filter_preset_name.clone(), filter: settings.object_filter.clone() };
+                match existing {
+                    Some(index) => settings.filter_presets[index] = preset,
+                    None => settings.filter_presets.push(preset)
+                }
+                changed = true;
+            }
+        });
+
+        ui.same_line();
+        ui.disabled(existing.is_none(), || {
+            if ui.button("Delete Preset") {
+                settings.filter_presets.remove(existing.unwrap());
+                changed = true;
+            }
+        });
+
+        if changed {
+            if let Err(e) = save_filter_presets(&config_path(FILTER_PRESETS_FILE), &settings.filter_presets) {
+                println!("{}", e);
+            }
+        }
+    }
+
+    if !settings.object_filter.is_active() {
+        return None;
+    }
+
+    match settings.object_filter.matcher(snapshot, &mut settings.filter_regex) {
+        Ok(matcher) => Some(matcher),
+        Err(e) => {
+            ui.text_colored(RED, format!("Filter not applied: {}", e));
+            None
+        }
+    }
+}
+
+// Columns of the Objects table in the order they are declared, imgui keeps track of the order they are shown in.
+#[derive(Clone, Copy, PartialEq)]
+enum ObjectColumn {
+    Set,
+    Datum,
+    Index,
+    Id,
+    Player,
+    Coordinates,
+    Flags,
+    TagName,
+    TagClass,
+    TagClassSecondary,
+    TagClassTertiary,
+    Distance,
+    Address,
+    TagIndex,
+    Spawned
+}
+
+const OBJECT_COLUMNS: [ObjectColumn; 15] = [
+    ObjectColumn::Set,
+    ObjectColumn::Datum,
+    ObjectColumn::Index,
+    ObjectColumn::Id,
+    ObjectColumn::Player,
+    ObjectColumn::Coordinates,
+    ObjectColumn::Flags,
+    ObjectColumn::TagName,
+    ObjectColumn::TagClass,
+    ObjectColumn::TagClassSecondary,
+    ObjectColumn::TagClassTertiary,
+    ObjectColumn::Distance,
+    ObjectColumn::Address,
+    ObjectColumn::TagIndex,
+    ObjectColumn::Spawned
+];
+
+impl ObjectColumn {
+    fn name(&self) -> &'static str {
+        match self {
+            ObjectColumn::Set => "",
+            ObjectColumn::Datum => "Datum",
+            ObjectColumn::Index => "Index",
+            ObjectColumn::Id => "ID",
+            ObjectColumn::Player => "Player",
+            ObjectColumn::Coordinates => "Coordinates",
+            ObjectColumn::Flags => "Flags",
+            ObjectColumn::TagName => "Tag Name",
+            ObjectColumn::TagClass => "Tag Class",
+            ObjectColumn::TagClassSecondary => "Tag Class Secondary",
+            ObjectColumn::TagClassTertiary => "Tag Class Tertiary",
+            ObjectColumn::Distance => "Distance To Player 0",
+            ObjectColumn::Address => "Object Address",
+            ObjectColumn::TagIndex => "Tag Index",
+            ObjectColumn::Spawned => "Spawned"
+        }
+    }
+
+    fn flags(&self) -> TableColumnFlags {
+        match self {
+            ObjectColumn::Set => TableColumnFlags::NO_SORT | TableColumnFlags::NO_HIDE | TableColumnFlags::NO_REORDER,
+            // Slots are listed from the top of the pool down until another column is picked.
+            ObjectColumn::Index => TableColumnFlags::DEFAULT_SORT | TableColumnFlags::PREFER_SORT_DESCENDING,
+            ObjectColumn::Coordinates => TableColumnFlags::NO_SORT,
+            // Decoded fields that are not shown until picked from the header context menu.
+            ObjectColumn::Distance | ObjectColumn::Address | ObjectColumn::TagIndex | ObjectColumn::Spawned => TableColumnFlags::DEFAULT_HIDE,
+            _ => TableColumnFlags::empty()
+        }
+    }
+}
+
+// Order two rows of the Objects table by a column, free slots sort before occupied ones.
+fn compare_object_rows(column: ObjectColumn, instance: &Instance, snapshot: &EngineSnapshot, origin: Option<[f32; 3]>, first: &(usize, Option<ObjectRow>), second: &(usize, Option<ObjectRow>)) -> Ordering {
+    let (first_index, first_row) = (first.0, first.1.as_ref());
+    let (second_index, second_row) = (second.0, second.1.as_ref());
+    let game_object = |index: usize| snapshot.game_object_entries.get(index).and_then(|entry| entry.as_ref());
+    let object_address = |index: usize| snapshot.object_pool_entries.get(index).and_then(|entry| entry.as_ref()).and_then(|entry| entry.game_object_address());
+    let row_distance = |row: Option<&ObjectRow>| origin.zip(row).map(|(origin, row)| distance(origin, row.position));
+
+    let ordering = match column {
+        ObjectColumn::Set | ObjectColumn::Index | ObjectColumn::Coordinates => Ordering::Equal,
+        ObjectColumn::Datum => first_row.map(|row| row.datum.get_handle()).cmp(&second_row.map(|row| row.datum.get_handle())),
+        ObjectColumn::Id => first_row.map(|row| row.id).cmp(&second_row.map(|row| row.id)),
+        ObjectColumn::Player => {
+            let player = |row: Option<&ObjectRow>| row.and_then(|row| row.player.or(row.dead_player));
+            player(first_row).cmp(&player(second_row))
+        }
+        ObjectColumn::Flags => game_object(first_index).map(|object| object.flags).cmp(&game_object(second_index).map(|object| object.flags)),
+        ObjectColumn::TagName => first_row.map(|row| &row.tag_name).cmp(&second_row.map(|row| &row.tag_name)),
+        ObjectColumn::TagClass => first_row.map(|row| &row.tag_class).cmp(&second_row.map(|row| &row.tag_class)),
+        ObjectColumn::TagClassSecondary => first_row.map(|row| &row.tag_class_secondary).cmp(&second_row.map(|row| &row.tag_class_secondary)),
+        ObjectColumn::TagClassTertiary => first_row.map(|row| &row.tag_class_tertiary).cmp(&second_row.map(|row| &row.tag_class_tertiary)),
+        ObjectColumn::Distance => match (row_distance(first_row), row_distance(second_row)) {
+            (Some(first), Some(second)) => first.total_cmp(&second),
+            (first, second) => first.is_some().cmp(&second.is_some())
+        },
+        ObjectColumn::Address => object_address(first_index).cmp(&object_address(second_index)),
+        ObjectColumn::TagIndex => game_object(first_index).map(|object| object.tag_index).cmp(&game_object(second_index).map(|object| object.tag_index)),
+        ObjectColumn::Spawned => {
+            let spawned = |row: Option<&ObjectRow>| row.and_then(|row| occupant_spawned(instance, &row.datum));
+            spawned(first_row).cmp(&spawned(second_row))
+        }
+    };
+
+    // Ties keep slot order.
+    ordering.then(first_index.cmp(&second_index))
+}
+
+// When the object in a slot was first seen, if it is still the same datum.
+fn occupant_spawned(instance: &Instance, datum: &DatumHandle) -> Option<Duration> {
+    instance.slot_history.occupant_at(datum.get_index() as usize, instance.session_timestamp())
+        .filter(|occupant| occupant.datum.get_handle() == datum.get_handle())
+        .map(|occupant| occupant.spawned)
+}
+
+// Flags the object in a slot was first seen with, if it is still the same datum.
+fn occupant_spawn_flags(instance: &Instance, datum: &DatumHandle) -> Option<u32> {
+    instance.slot_history.occupant_at(datum.get_index() as usize, instance.session_timestamp())
+        .filter(|occupant| occupant.datum.get_handle() == datum.get_handle())
+        .map(|occupant| occupant.spawn_flags)
+}
+
+// Named flag bits of the target object, bits that differ from its spawn state are highlighted.
+fn draw_object_flags(ui: &Ui, settings: &Settings, instance: &mut Instance, snapshot: &EngineSnapshot) {
+    let index = instance.target_index as usize;
+    let (row, flags) = match (object_row(snapshot, index), snapshot.game_object_entries.get(index).and_then(|entry| entry.as_ref())) {
+        (Some(row), Some(game_object)) => (row, game_object.flags),
+        _ => {
+            ui.text(format!("Slot {} is empty, pick a target with Set.", index));
+            return;
+        }
+    };
+
+    let editable = instance.memory.is_some() && !settings.read_only;
+    let spawn_flags = occupant_spawn_flags(instance, &row.datum);
+    let flags_freeze = instance.freezes.find_object(&row.datum, FreezeField::Flags);
+
+    ui.text(format!("{} {}", row.datum.get_handle(), row.tag_name));
+    match spawn_flags {
+        Some(spawn_flags) => ui.text(format!("Flags: {:08X}, spawned with {:08X}", flags, spawn_flags)),
+        None => ui.text(format!("Flags: {:08X}", flags))
+    }
+
+    let mut updated_flags = None;
+    let mut toggled_bit = None;
+
+    if let Some(spawn_flags) = spawn_flags {
+        ui.disabled(!editable || spawn_flags == flags, || {
+            if ui.button("Reset To Spawn Flags") {
+                updated_flags = Some((spawn_flags, "reset flags".to_string()));
+            }
+        });
+    }
+
+    if let Some(table) = ui.begin_table_with_flags("ObjectFlagsTable", 2, TableFlags::SIZING_STRETCH_SAME) {
+        for bit in 0..OBJECT_FLAG_BITS {
+            ui.table_next_column();
+
+            let name = object_flag_name(bit);
+            let changed = spawn_flags.is_some_and(|spawn_flags| is_flag_set(spawn_flags ^ flags, bit));
+            let mut set = is_flag_set(flags, bit);
+
+            let color = ui.push_style_color(StyleColor::Text, if changed { ORANGE } else { WHITE });
+            ui.disabled(!editable, || {
+                if ui.checkbox(format!("{}##flag{}", name, bit), &mut set) {
+                    let action = if set { "set" } else { "clear" };
+                    toggled_bit = Some((bit, set, format!("{} {}", action, name)));
+                }
+            });
+            color.pop();
+        }
+
+        table.end();
+    }
+
+    if let Some((updated_flags, action)) = updated_flags {
+        let bytes = updated_flags.to_le_bytes().to_vec();
+
+        // Frozen flags follow the edit instead of snapping back.
+        if let Some(freeze_index) = flags_freeze {
+            instance.freezes.values[freeze_index].bytes = bytes.clone();
+        }
+
+        let description = format!("{} {} {}", row.datum.get_handle(), row.tag_name, action);
+        let result = instance.write_object(&row.datum, GAME_OBJECT_FLAGS_OFFSET, &bytes, description, snapshot.game_tick);
+        instance.report_write(result);
+    }
+
+    // Only the toggled bit changes, the rest of the word is taken from the object at the time of the write.
+    if let Some((bit, set, action)) = toggled_bit {
+        let description = format!("{} {} {}", row.datum.get_handle(), row.tag_name, action);
+        match instance.write_object_flag(&row.datum, bit, set, description, snapshot.game_tick) {
+            Ok(written) => {
+                if let Some(freeze_index) = flags_freeze {
+                    instance.freezes.values[freeze_index].bytes = written.to_le_bytes().to_vec();
+                }
+            }
+            Err(e) => instance.report_write(Err(e))
+        }
+    }
+}
+
+static FREEZE_KINDS: [&str; 4] = ["Target Position", "Target Flags", "Target Offset", "Address"];
+
+// Values written back after every sample. Object freezes follow the target slot's datum and are dropped once the slot is reused.
+fn draw_freezes(ui: &Ui, settings: &mut Settings, instance: &mut Instance, snapshot: &EngineSnapshot) {
+    let editable = instance.memory.is_some() && !settings.read_only;
+    if settings.read_only {
+        ui.text_colored(ORANGE, "Frozen values are not written while read only.");
+    }
+
+    let target_index = instance.target_index as usize;
+    let target = snapshot.object_pool_entries.get(target_index).and_then(|entry| entry.as_ref())
+        .zip(snapshot.game_object_entries.get(target_index).and_then(|entry| entry.as_ref()));
+
+    let preview = FREEZE_KINDS[settings.freeze_kind];
+    if let Some(combo) = ui.begin_combo("Freeze", preview) {
+        for (kind, name) in FREEZE_KINDS.iter().enumerate() {
+            if ui.selectable_config(name).selected(kind == settings.freeze_kind).build() {
+                settings.freeze_kind = kind;
+            }
+        }
+        combo.end();
+    }
+
+    // Presets take the current value from the snapshot, offsets and addresses need one typed in.
+    if settings.freeze_kind >= 2 {
+        let offset_label = if settings.freeze_kind == 2 { "Offset (hex)" } else { "Address (hex)" };
+        ui.input_text(offset_label, &mut settings.freeze_offset).allow_tab_input(false).build();
+        ui.input_text("Bytes (hex)", &mut settings.freeze_value).allow_tab_input(false).build();
+    }
+
+    ui.disabled(!editable, || {
+        if ui.button("Add Freeze") {
+            let datum = target.map(|(entry, _)| DatumHandle::new_from_index_id(target_index as u16, entry.id));
+            let offset = usize::from_str_radix(settings.freeze_offset.trim().trim_start_matches("0x"), 16).ok();
+            let value = parse_hex_bytes(&settings.freeze_value);
+
+            let freeze = match (settings.freeze_kind, datum, target) {
+                (0, Some(datum), Some((_, game_object))) => {
+                    let bytes = game_object.position.iter().flat_map(|value| value.to_le_bytes()).collect();
+                    Ok((FreezeTarget::Object { datum: datum, field: FreezeField::Position }, bytes))
+                }
+                (1, Some(datum), Some((_, game_object))) => {
+                    Ok((FreezeTarget::Object { datum: datum, field: FreezeField::Flags }, game_object.flags.to_le_bytes().to_vec()))
+                }
+                (0..=2, None, _) | (0..=2, _, None) => Err(format!("Slot {} is empty, pick a target with Set.", target_index)),
+                (2, Some(datum), _) => match (offset, value) {
+                    (Some(offset), Some(value)) => Ok((FreezeTarget::Object { datum: datum, field: FreezeField::Custom(offset) }, value)),
+                    _ => Err("Enter an offset and the bytes to freeze as hex.".to_string())
+                },
+                (_, _, _) => match (offset, value) {
+                    (Some(address), Some(value)) => Ok((FreezeTarget::Address(address), value)),
+                    _ => Err("Enter an address and the bytes to freeze as hex.".to_string())
+                }
+            };
+
+            match freeze {
+                Ok((target, bytes)) => {
+                    let label = match &target {
+                        FreezeTarget::Object { datum, field } => {
+                            let tag_name = object_row(snapshot, target_index).map(|row| row.tag_name).unwrap_or_default();
+                            format!("{} {} {}", datum.get_handle(), tag_name, field.name().to_lowercase())
+                        }
+                        FreezeTarget::Address(address) => format!("{:#X}", address)
+                    };
+
+                    instance.freezes.add(label, target, bytes);
+                    settings.freeze_error = None;
+                }
+                Err(e) => settings.freeze_error = Some(e)
+            }
+        }
+    });
+
+    if let Some(error) = &settings.freeze_error {
+        ui.text_colored(RED, error);
+    }
+
+    for label in &instance.freezes.dropped {
+        ui.text_colored(ORANGE, format!("Dropped \"{}\", its slot was reused.", label));
+    }
+
+    if instance.freezes.values.is_empty() {
+        ui.text("Nothing frozen.");
+        return;
+    }
+
+    let mut remove = None;
+    if let Some(table) = ui.begin_table_with_flags("FrozenValuesTable", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H) {
+        ui.table_setup_column("On");
+        ui.table_setup_column("Value");
+        ui.table_setup_column("Bytes");
+        ui.table_setup_column("");
+        ui.table_headers_row();
+
+        for (index, value) in instance.freezes.values.iter_mut().enumerate() {
+            let identity = ui.push_id_usize(index);
+            ui.table_next_row();
+
+            ui.table_set_column_index(0);
+            ui.checkbox("##Enabled", &mut value.enabled);
+
+            ui.table_next_column();
+            ui.text(&value.label);
+
+            ui.table_next_column();
+            ui.text(value.bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<String>());
+
+            ui.table_next_column();
+            if ui.small_button("Remove") {
+                remove = Some(index);
+            }
+
+            identity.pop();
+        }
+
+        table.end();
+    }
+
+    if let Some(index) = remove {
+        instance.freezes.remove(index);
+    }
+}
+
+static WATCH_KINDS: [&str; 4] = ["Target Datum", "Target Slot", "Player Field", "Address"];
+
+// Watchlists are kept per map, a snapshot without a scenario tag goes under UNKNOWN_MAP.
+fn watchlist_map(snapshot: &EngineSnapshot) -> String {
+    snapshot.scenario_path().unwrap_or(UNKNOWN_MAP).to_string()
+}
+
+fn add_watch(settings: &mut Settings, snapshot: &EngineSnapshot, label: String, target: WatchTarget) {
+    settings.watchlists.entry(watchlist_map(snapshot)).or_default().push(Watch::new(label, target));
+    settings.watch_error = save_watchlists(&config_path(WATCHLISTS_FILE), &settings.watchlists).err();
+}
+
+// Pinned objects, player fields and addresses of the current map, with their live values.
+fn draw_watchlist(ui: &Ui, settings: &mut Settings, instance: &mut Instance, snapshot: &EngineSnapshot) {
+    let map = watchlist_map(snapshot);
+    ui.text(format!("Map: {}", map));
+
+    let preview = WATCH_KINDS[settings.watch_kind];
+    if let Some(combo) = ui.begin_combo("Watch", preview) {
+        for (kind, name) in WATCH_KINDS.iter().enumerate() {
+            if ui.selectable_config(name).selected(kind == settings.watch_kind).build() {
+                settings.watch_kind = kind;
+            }
+        }
+        combo.end();
+    }
+
+    if settings.watch_kind == 2 {
+        ui.input_int("Local Player", &mut settings.watch_player).build();
+        if let Some(combo) = ui.begin_combo("Field", PLAYER_FIELDS[settings.watch_player_field].name()) {
+            for (index, field) in PLAYER_FIELDS.iter().enumerate() {
+                if ui.selectable_config(field.name()).selected(index == settings.watch_player_field).build() {
+                    settings.watch_player_field = index;
+                }
+            }
+            combo.end();
+        }
+    } else if settings.watch_kind == 3 {
+        ui.input_text("Address (hex)", &mut settings.watch_address).allow_tab_input(false).build();
+        if let Some(combo) = ui.begin_combo("Type", VALUE_KINDS[settings.watch_value_kind].name()) {
+            for (index, kind) in VALUE_KINDS.iter().enumerate() {
+                if ui.selectable_config(kind.name()).selected(index == settings.watch_value_kind).build() {
+                    settings.watch_value_kind = index;
+                }
+            }
+            combo.end();
+        }
+    }
+
+    ui.input_text("Label", &mut settings.watch_label).hint("Defaults to what is watched").build();
+
+    if ui.button("Add Watch") {
+        let target_index = instance.target_index as usize;
+        let target_entry = snapshot.object_pool_entries.get(target_index).and_then(|entry| entry.as_ref());
+
+        let target = match settings.watch_kind {
+            0 => target_entry
+                .map(|entry| WatchTarget::Datum(DatumHandle::new_from_index_id(target_index as u16, entry.id)))
+                .ok_or(format!("Slot {} is empty, pick a target with Set.", target_index)),
+            1 => Ok(WatchTarget::Slot(target_index as u16)),
+            2 => match usize::try_from(settings.watch_player) {
+                Ok(local_player) if local_player < snapshot.player_globals.local_players.len() => {
+                    Ok(WatchTarget::Player { local_player: local_player, field: PLAYER_FIELDS[settings.watch_player_field] })
+                }
+                _ => Err(format!("Local players go from 0 to {}.", snapshot.player_globals.local_players.len() - 1))
+            },
+            _ => usize::from_str_radix(settings.watch_address.trim().trim_start_matches("0x"), 16)
+                .map(|address| WatchTarget::Address { address: address, kind: VALUE_KINDS[settings.watch_value_kind] })
+                .map_err(|_| "Enter the address as hex.".to_string())
+        };
+
+        match target {
+            Ok(target) => {
+                let label = match settings.watch_label.trim() {
+                    "" => match &target {
+                        WatchTarget::Datum(_) | WatchTarget::Slot(_) => {
+                            let tag_name = object_row(snapshot, target_index).map(|row| row.tag_name).unwrap_or_default();
+                            format!("{} {}", target.name(), tag_name)
+                        }
+                        _ => target.name()
+                    },
+                    label => label.to_string()
+                };
+
+                add_watch(settings, snapshot, label, target);
+                settings.watch_label.clear();
+            }
+            Err(e) => settings.watch_error = Some(e)
+        }
+    }
+
+    if let Some(error) = &settings.watch_error {
+        ui.text_colored(RED, error);
+    }
+
+    let watches = match settings.watchlists.get_mut(&map) {
+        Some(watches) if !watches.is_empty() => watches,
+        _ => {
+            ui.text("Nothing watched on this map, right click Set in the Objects table to watch an object.");
+            return;
+        }
+    };
+
+    let mut remove = None;
+    if let Some(table) = ui.begin_table_with_flags("WatchlistTable", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H | TableFlags::RESIZABLE) {
+        ui.table_setup_column("Label");
+        ui.table_setup_column("Value");
+        ui.table_setup_column("Changed");
+        ui.table_setup_column("");
+        ui.table_headers_row();
+
+        for (index, watch) in watches.iter_mut().enumerate() {
+            watch.update(snapshot, instance.memory.as_ref());
+
+            let identity = ui.push_id_usize(index);
+            ui.table_next_row();
+
+            ui.table_set_column_index(0);
+            ui.text(&watch.label);
+            if ui.is_item_hovered() {
+                ui.tooltip_text(watch.target.name());
+            }
+
+            let since_change = watch.changed_at.map(|changed_at| changed_at.elapsed());
+            let color = if since_change.is_some_and(|elapsed| elapsed < WATCH_CHANGE_DURATION) { ORANGE } else { WHITE };
+
+            ui.table_next_column();
+            ui.text_colored(color, watch.value.as_deref().unwrap_or_default());
+
+            ui.table_next_column();
+            if let Some(elapsed) = since_change {
+                ui.text_colored(color, format!("{:.1} s ago", elapsed.as_secs_f32()));
+            }
+
+            ui.table_next_column();
+            if ui.small_button("Remove") {
+                remove = Some(index);
+            }
+
+            identity.pop();
+        }
+
+        table.end();
+    }
+
+    if let Some(index) = remove {
+        watches.remove(index);
+        settings.watch_error = save_watchlists(&config_path(WATCHLISTS_FILE), &settings.watchlists).err();
+    }
+}
+
+fn save_rules(settings: &mut Settings) {
+    settings.alert_error = save_alert_rules(&config_path(ALERT_RULES_FILE), &settings.alert_rules).err();
+}
+
+// Rules checked against every new live snapshot, kept in ALERT_RULES_FILE so they can also be written by hand.
+fn draw_alert_rules(ui: &Ui, settings: &mut Settings, instance: &Instance) {
+    ui.text_disabled(format!("Rules file: {}", config_path(ALERT_RULES_FILE).display()));
+    if ui.button("Reload From File") {
+        match load_alert_rules(&config_path(ALERT_RULES_FILE)) {
+            Ok(rules) => {
+                settings.alert_rules = rules;
+                settings.alert_error = None;
+            }
+            Err(e) => settings.alert_error = Some(e)
+        }
+    }
+
+    ui.separator();
+    ui.input_text("Name", &mut settings.alert_name).hint("Defaults to the condition").build();
+
+    let preview = ALERT_CONDITIONS[settings.alert_condition];
+    if let Some(combo) = ui.begin_combo("When", preview) {
+        for (kind, name) in ALERT_CONDITIONS.iter().enumerate() {
+            if ui.selectable_config(name).selected(kind == settings.alert_condition).build() {
+                settings.alert_condition = kind;
+            }
+        }
+        combo.end();
+    }
+
+    // Slot Freed and Tag Spawned name a slot, Tag Spawned also takes -1 for any slot.
+    if settings.alert_condition == 0 || settings.alert_condition == 2 {
+        ui.input_int("Slot", &mut settings.alert_slot).build();
+        ui.same_line();
+        if ui.button("Target") {
+            settings.alert_slot = instance.target_index as i32;
+        }
+    }
+
+    if settings.alert_condition == 2 {
+        ui.input_text("Tag Path", &mut settings.alert_tag_path).hint("Part of the tag path").build();
+    }
+
+    if settings.alert_condition == 3 || settings.alert_condition == 4 {
+        ui.input_int(if settings.alert_condition == 3 { "Object ID" } else { "Threshold" }, &mut settings.alert_value).build();
+    }
+
+    ui.checkbox("Toast", &mut settings.alert_actions.toast);
+    ui.same_line();
+    ui.checkbox("Sound", &mut settings.alert_actions.sound);
+    ui.same_line();
+    ui.checkbox("Log", &mut settings.alert_actions.log);
+    ui.input_text("Write Address (hex)", &mut settings.alert_write_address).hint("Optional").allow_tab_input(false).build();
+    ui.input_text("Write Bytes (hex)", &mut settings.alert_write_bytes).allow_tab_input(false).build();
+
+    if ui.button("Add Rule") {
+        let slot = u16::try_from(settings.alert_slot).ok();
+        let value = u16::try_from(settings.alert_value).ok();
+
+        let condition = match (settings.alert_condition, slot, value) {
+            (0, Some(slot), _) => Ok(AlertCondition::SlotFreed { slot: slot }),
+            (0, None, _) => Err("Enter the slot to watch.".to_string()),
+            (1, _, _) => Ok(AlertCondition::RespawnFailureChanged),
+            (2, _, _) if settings.alert_tag_path.trim().is_empty() => Err("Enter part of the tag path to look for.".to_string()),
+            (2, slot, _) => Ok(AlertCondition::TagSpawned { slot: slot, tag_path: settings.alert_tag_path.trim().to_string() }),
+            (3, _, Some(value)) => Ok(AlertCondition::NextObjectIdReaches { value: value }),
+            (4, _, Some(value)) => Ok(AlertCondition::ObjectCountCrosses { threshold: value }),
+            (_, _, _) => Err(format!("Enter a value from 0 to {}.", u16::MAX))
+        };
+
+        let write = match settings.alert_write_address.trim() {
+            "" => Ok(None),
+            address => match (usize::from_str_radix(address.trim_start_matches("0x"), 16), parse_hex_bytes(&settings.alert_write_bytes)) {
+                (Ok(address), Some(bytes)) => Ok(Some(AlertWrite { address: address, bytes: bytes })),
+                _ => Err("Enter the address and the bytes to write as hex.".to_string())
+            }
+        };
+
+        match condition.and_then(|condition| write.map(|write| (condition, write))) {
+            Ok((condition, write)) => {
+                let name = match settings.alert_name.trim() {
+                    "" => condition.describe(),
+                    name => name.to_string()
+                };
+
+                let mut actions = settings.alert_actions.clone();
+                actions.write = write;
+                settings.alert_rules.push(AlertRule { name: name, enabled: true, condition: condition, actions: actions });
+                settings.alert_name.clear();
+                save_rules(settings);
+            }
+            Err(e) => settings.alert_error = Some(e)
+        }
+    }
+
+    if let Some(error) = &settings.alert_error {
+        ui.text_colored(RED, error);
+    }
+
+    if instance.playback.is_some() {
+        ui.text_colored(ORANGE, "Rules only run on live snapshots, not recordings.");
+    }
+
+    if settings.alert_rules.is_empty() {
+        ui.text("No rules.");
+        return;
+    }
+
+    let mut changed = false;
+    let mut remove = None;
+    if let Some(table) = ui.begin_table_with_flags("AlertRulesTable", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H) {
+        ui.table_setup_column("On");
+        ui.table_setup_column("Rule");
+        ui.table_setup_column("Actions");
+        ui.table_setup_column("");
+        ui.table_headers_row();
+
+        for (index, rule) in settings.alert_rules.iter_mut().enumerate() {
+            let identity = ui.push_id_usize(index);
+            ui.table_next_row();
+
+            ui.table_set_column_index(0);
+            changed |= ui.checkbox("##Enabled", &mut rule.enabled);
+
+            ui.table_next_column();
+            ui.text(&rule.name);
+            if ui.is_item_hovered() {
+                ui.tooltip_text(rule.condition.describe());
+            }
+
+            ui.table_next_column();
+            let mut actions = Vec::new();
+            for (enabled, name) in [(rule.actions.toast, "Toast"), (rule.actions.sound, "Sound"), (rule.actions.log, "Log")] {
+                if enabled {
+                    actions.push(name.to_string());
+                }
+            }
+            if let Some(write) = &rule.actions.write {
+                actions.push(format!("Write {:#X}", write.address));
+            }
+            ui.text(actions.join(", "));
+
+            ui.table_next_column();
+            if ui.small_button("Remove") {
+                remove = Some(index);
+            }
+
+            identity.pop();
+        }
+
+        table.end();
+    }
+
+    if let Some(index) = remove {
+        settings.alert_rules.remove(index);
+        changed = true;
+    }
+
+    if changed {
+        save_rules(settings);
+    }
+}
+
+// Alert toasts of every instance in the top right corner, newest at the bottom.
+fn draw_toasts(ui: &Ui, instances: &mut [Instance]) {
+    let named = instances.len() > 1;
+    let mut toasts = Vec::new();
+
+    for instance in instances.iter_mut() {
+        instance.alert_log.toasts.retain(|toast| toast.shown_at.elapsed() < TOAST_DURATION);
+        for toast in &instance.alert_log.toasts {
+            toasts.push(if named { format!("{}: {}", instance.label, toast.text) } else { toast.text.clone() });
+        }
+    }
+
+    if toasts.is_empty() {
+        return;
+    }
+
+    let width = ui.io().display_size[0];
+    let flags = WindowFlags::NO_DECORATION | WindowFlags::ALWAYS_AUTO_RESIZE | WindowFlags::NO_SAVED_SETTINGS | WindowFlags::NO_FOCUS_ON_APPEARING
+        | WindowFlags::NO_NAV | WindowFlags::NO_DOCKING | WindowFlags::NO_MOVE;
+
+    let toast_window = ui.window("Toasts")
+        .position([width - 10.0, 30.0], Condition::Always)
+        .position_pivot([1.0, 0.0])
+        .bg_alpha(0.85)
+        .flags(flags)
+        .begin();
+
+    if let Some(toast_window) = toast_window {
+        for toast in toasts {
+            ui.text_colored(ORANGE, toast);
+        }
+        toast_window.end();
+    }
+}
+
+// Every write made to this instance, undone entries are greyed out until they are redone or overwritten.
+fn draw_journal(ui: &Ui, settings: &mut Settings, instance: &mut Instance, tick: Option<u32>) {
+    let editable = instance.memory.is_some() && !settings.read_only;
+
+    ui.disabled(!editable || !instance.journal.can_undo(), || {
+        if ui.button("Undo") {
+            let result = instance.undo();
+            instance.report_write(result);
+        }
+    });
+    ui.same_line();
+    ui.disabled(!editable || !instance.journal.can_redo(), || {
+        if ui.button("Redo") {
+            let result = instance.redo();
+            instance.report_write(result);
+        }
+    });
+
+    ui.input_text("Journal Path", &mut settings.journal_path)
+        .allow_tab_input(false)
+        .build();
+
+    if ui.button("Export") {
+        let path = PathBuf::from(&settings.journal_path);
+        instance.journal_status = Some(match instance.journal.export(&path) {
+            Ok(()) => format!("Exported {} writes to {}.", instance.journal.position(), path.display()),
+            Err(e) => e
+        });
+    }
+    ui.same_line();
+    ui.disabled(!editable, || {
+        if ui.button("Replay") {
+            let path = PathBuf::from(&settings.journal_path);
+            instance.journal_status = Some(match instance.replay_journal(&path, tick) {
+                Ok(count) => format!("Replayed {} writes from {}.", count, path.display()),
+                Err(e) => e
+            });
+        }
+    });
+
+    if let Some(status) = &instance.journal_status {
+        ui.text_wrapped(status);
+    }
+
+    if instance.journal.entries().is_empty() {
+        ui.text("No writes made yet.");
+        return;
+    }
+
+    if let Some(table) = ui.begin_table_with_flags("WriteJournalTable", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H) {
+        ui.table_setup_column("Time");
+        ui.table_setup_column("Address");
+        ui.table_setup_column("Write");
+        ui.table_setup_column("Bytes");
+        ui.table_headers_row();
+
+        let position = instance.journal.position();
+        for (index, entry) in instance.journal.entries().iter().enumerate().rev() {
+            let color = if index < position { WHITE } else { GREY };
+            ui.table_next_row();
+
+            ui.table_set_column_index(0);
+            ui.text_colored(color, format_event_time(Duration::from_millis(entry.timestamp_ms), entry.tick));
+
+            ui.table_next_column();
+            ui.text_colored(color, format!("{:#X}", entry.address));
+
+            ui.table_next_column();
+            ui.text_colored(color, &entry.description);
+
+            ui.table_next_column();
+            ui.text_colored(color, format!("{}", entry.new_bytes.len()));
+            if ui.is_item_hovered() {
+                ui.tooltip_text(format!("Old: {:02X?}\nNew: {:02X?}", entry.old_bytes, entry.new_bytes));
+            }
+        }
+
+        table.end();
+    }
+}
+
+// Every instance keeps sampling and playing back while its tab is in the background or the attach screen is open.
+// Otherwise the sampler queue fills up and stalls.
+fn update_instances(ui: &Ui, draw_context: &mut DrawContext) {
+    let elapsed = Duration::from_secs_f32(ui.io().delta_time);
+    let claimed_pids: Vec<u32> = draw_context.instances.iter().filter_map(|instance| instance.pid()).collect();
+    for instance in draw_context.instances.iter_mut() {
+        instance.update(draw_context.settings.sample_settings(), draw_context.settings.read_only, &draw_context.settings.alert_rules, elapsed, &claimed_pids);
+    }
+}
+
+fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
+    let active = draw_context.active_instance.min(draw_context.instances.len() - 1);
+    let mut tabs = InstanceTabs {
+        labels: draw_context.instances.iter().map(|instance| instance.label.clone()).collect(),
+        active: active,
+        select: draw_context.select_instance.take(),
+        close: None,
+        attach_another: false
+    };
+
+    let instance = &mut draw_context.instances[active];
+
+    // Undo and redo the last write, unless a text field has the keyboard.
+    if instance.memory.is_some() && !draw_context.settings.read_only && ui.io().key_ctrl && !ui.io().want_text_input {
+        if ui.is_key_pressed(Key::Z) {
+            let result = instance.undo();
+            instance.report_write(result);
+        } else if ui.is_key_pressed(Key::Y) {
+            let result = instance.redo();
+            instance.report_write(result);
+        }
+    }
+
+    let snapshot = instance.snapshot.take();
+    draw_snapshot(ui, should_exit, &mut draw_context.settings, &mut tabs, instance, snapshot.as_ref());
+    instance.snapshot = snapshot;
+
+    draw_context.active_instance = tabs.active;
+    draw_context.show_attach = tabs.attach_another;
+
+    draw_toasts(ui, &mut draw_context.instances);
+
+    if let Some(index) = tabs.close {
+        draw_context.instances.remove(index);
+        if draw_context.active_instance >= index && draw_context.active_instance > 0 {
+            draw_context.active_instance -= 1;
+        }
+        draw_context.select_instance = Some(draw_context.active_instance);
+    }
+}
+
+// One tab per attached process or opened recording.
+fn draw_instance_tabs(ui: &Ui, tabs: &mut InstanceTabs) {
+    if let Some(tab_bar) = ui.tab_bar("Instances") {
+        for (index, label) in tabs.labels.iter().enumerate() {
+            let mut opened = true;
+            let flags = if tabs.select == Some(index) { TabItemFlags::SET_SELECTED } else { TabItemFlags::empty() };
 
-                ui.text_colored(ORANGE, format!("Position: X: {:.4} Y: {:.4} Z: {:.4}", game_object_entry.position[0], game_object_entry.position[1], game_object_entry.position[2]));
-                found = true;
+            if let Some(tab) = ui.tab_item_with_flags(format!("{}###instance{}", label, index), Some(&mut opened), flags) {
+                tabs.active = index;
+                tab.end();
             }
-    
-            if !found {
-                ui.text_colored(ORANGE, format!("Position: None"));
+
+            if !opened {
+                tabs.close = Some(index);
             }
         }
-    } else { 
-        ui.text_colored(ORANGE, format!("Unit Handle: None"));
-        ui.text_colored(ORANGE, format!("Position: None"));
-    }
 
-    let handle = &snapshot.player_globals.local_dead_players[player_index as usize];
-    {
-        let index = handle.get_index() as usize;
+        tab_bar.end();
+    }
+}
 
-        let mut found = false;
-        if snapshot.object_pool_entries.get(index).is_some() && 
-           snapshot.game_object_entries.get(index).is_some() && 
-           snapshot.object_pool_entries[index].as_ref().is_some() && 
-           snapshot.game_object_entries[index].as_ref().is_some() 
-        {
-            let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+// Named window layouts, applied by the main loop once the frame is done.
+fn draw_layout_menu(ui: &Ui, settings: &mut Settings) {
+    if settings.layout_presets.is_empty() {
+        ui.text_disabled("No saved layouts");
+    }
 
-            ui.text_colored(ORANGE, format!("Next Datum Position: X: {:.4} Y: {:.4} Z: {:.4}", game_object_entry.position[0], game_object_entry.position[1], game_object_entry.position[2]));
-            found = true;
+    for preset in &settings.layout_presets {
+        if ui.menu_item(&preset.name) {
+            settings.layout_request = Some(LayoutRequest::Load(preset.name.clone()));
         }
+    }
 
-        if !found {
-            ui.text_colored(ORANGE, format!("Next Datum Position: None"));
+    ui.separator();
+    ui.set_next_item_width(150.0);
+    ui.input_text("##LayoutName", &mut settings.layout_name).hint("Layout name").build();
+    ui.same_line();
+    if ui.button("Save") && !settings.layout_name.trim().is_empty() {
+        settings.layout_request = Some(LayoutRequest::Save(settings.layout_name.trim().to_string()));
+    }
+
+    if !settings.layout_presets.is_empty() {
+        if let Some(token) = ui.begin_menu("Delete") {
+            for preset in &settings.layout_presets {
+                if ui.menu_item(&preset.name) {
+                    settings.layout_request = Some(LayoutRequest::Delete(preset.name.clone()));
+                }
+            }
+            token.end();
         }
     }
 
-    if let Some(entry) = snapshot.player_pool_entries[player_index as usize].as_ref() {
-    } else { 
-        ui.text_colored(ORANGE, format!("Last Unit Handle: None")) 
+    if let Some(error) = &settings.layout_error {
+        ui.text_colored(RED, error);
     }
 }
 
-fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
-    let memory_bytes = draw_context.memory.as_mut().unwrap().read();
-    let snapshot = build_snapshot(memory_bytes);
-
+fn draw_snapshot(ui: &mut Ui, should_exit: &mut bool, settings: &mut Settings, tabs: &mut InstanceTabs, instance: &mut Instance, snapshot: Option<&EngineSnapshot>) {
     // Do not render anything if the snapshot is invalid.
     let width = ui.io().display_size[0];
     let height = ui.io().display_size[1];
@@ -131,12 +1448,109 @@ fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
             if ui.menu_item("Close") {
                 *should_exit = true;
             };
+            if let Some(snapshot) = snapshot {
+                if ui.menu_item("Save Snapshot") {
+                    save_current_snapshot(snapshot);
+                }
+            }
+            if instance.memory.is_some() {
+                if ui.menu_item_config("Read Only").selected(settings.read_only).build() {
+                    settings.read_only = !settings.read_only;
+                }
+            }
+            if ui.menu_item("Attach Another...") {
+                tabs.attach_another = true;
+            }
             if ui.menu_item("Detach") {
-                draw_context.memory = None;
+                tabs.close = Some(tabs.active);
             };
             token.end();
         }
 
+        if let Some(token) = ui.begin_menu("View") {
+            for theme in THEMES {
+                if ui.menu_item_config(theme.name()).selected(settings.theme == theme).build() {
+                    settings.theme = theme;
+                }
+            }
+
+            ui.separator();
+            ui.menu_item_config("Players Globals").build_with_ref(&mut settings.show_players);
+            ui.menu_item_config("Inspector").build_with_ref(&mut settings.show_inspector);
+            ui.menu_item_config("Watchlist").build_with_ref(&mut settings.show_watchlist);
+            ui.menu_item_config("Alerts").build_with_ref(&mut settings.show_alerts);
+            ui.menu_item_config("Logs").build_with_ref(&mut settings.show_logs);
+            token.end();
+        }
+
+        if let Some(token) = ui.begin_menu("Layout") {
+            draw_layout_menu(ui, settings);
+            token.end();
+        }
+
+        // Recording only makes sense for a live process.
+        if instance.memory.is_some() {
+            if let Some(token) = ui.begin_menu("Session") {
+                if instance.recorder.is_some() {
+                    if ui.menu_item("Stop Recording") {
+                        instance.recorder = None;
+                    }
+                } else if ui.menu_item("Start Recording") {
+                    instance.start_recording();
+                }
+
+                ui.separator();
+                ui.set_next_item_width(150.0);
+                ui.slider_config("Sample Rate (Hz)", 1.0, 240.0).display_format("%.0f").build(&mut settings.sample_rate);
+                if ui.menu_item_config("Sync to Game Ticks").selected(settings.tick_sync).build() {
+                    settings.tick_sync = !settings.tick_sync;
+                }
+                if ui.menu_item_config("Pause Emulator While Reading").selected(settings.suspend_while_reading).build() {
+                    settings.suspend_while_reading = !settings.suspend_while_reading;
+                    instance.longest_pause = Duration::ZERO;
+                }
+                token.end();
+            }
+        }
+
+        if instance.sampler.is_some() {
+            ui.text(" | ");
+            ui.text(format!("{} KB/sample", instance.sample_bytes / 1024));
+        }
+
+        if let Some(pause) = instance.last_pause {
+            ui.text(" | ");
+            ui.text(format!("Paused {:.2} ms (max {:.2} ms)", pause.as_secs_f32() * 1000.0, instance.longest_pause.as_secs_f32() * 1000.0));
+        }
+
+        if let Some(tick) = snapshot.and_then(|snapshot| snapshot.game_tick) {
+            ui.text(" | ");
+            ui.text_colored(ORANGE, format!("Tick {}", tick));
+        } else if let Some(error) = &instance.calibration_error {
+            ui.text(" | ");
+            ui.text_colored(RED, error);
+        } else if settings.tick_sync && instance.sampler.is_some() {
+            ui.text(" | ");
+            ui.text("Locating game time");
+        }
+
+        if let Some((refused_at, error)) = &instance.write_error {
+            if refused_at.elapsed() < WRITE_ERROR_DURATION {
+                ui.text(" | ");
+                ui.text_colored(RED, error);
+            }
+        }
+
+        if let Some(status) = instance.link_status() {
+            ui.text(" | ");
+            ui.text_colored(RED, status);
+        }
+
+        if let Some(recorder) = &instance.recorder {
+            ui.text(" | ");
+            ui.text_colored(RED, format!("REC {} ({} frames, {})", recorder.path().display(), recorder.frame_count(), format_timestamp(recorder.elapsed())));
+        }
+
         if let Some(snapshot) = &snapshot {
             ui.text(" | ");
             ui.text_colored(ORANGE, format!("Next Object Index: {} ({})", snapshot.object_pool_header.next_object_index, first_free_index));
@@ -144,20 +1558,34 @@ fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
         }
     });    
 
-    let main_window = ui.window("Objects")
-        .size([width - 450.0, height - 20.0], Condition::Always)
-        .position([0.0, 20.0], Condition::Always)
-        .resizable(false)
-        .collapsible(false)
-        .begin();
+    // Leave room at the bottom for the playback timeline.
+    // Windows start out where the viewer used to pin them, after that they are wherever they were docked or dragged.
+    ui.dockspace_over_main_viewport();
 
-    let players_window = ui.window("Players Globals")
-        .size([450.0, height - 20.0], Condition::Always)
-        .position([width - 450.0, 20.0], Condition::Always)
-        .resizable(false)
-        .collapsible(false)
+    let timeline_height = if instance.playback.is_some() { TIMELINE_HEIGHT } else { 0.0 };
+
+    if let Some(playback) = instance.playback.as_mut() {
+        let timeline_window = ui.window("Timeline")
+            .size([width, timeline_height], Condition::FirstUseEver)
+            .position([0.0, height - timeline_height], Condition::FirstUseEver)
+            .begin();
+
+        if let Some(timeline_window) = timeline_window {
+            draw_timeline(ui, playback);
+            timeline_window.end();
+        }
+    }
+
+    let side_panel_height = (height - 20.0 - timeline_height) / 5.0;
+
+    let main_window = ui.window("Objects")
+        .size([width - SIDE_PANEL_WIDTH, height - 20.0 - timeline_height], Condition::FirstUseEver)
+        .position([0.0, 20.0], Condition::FirstUseEver)
         .begin();
 
+    if main_window.is_some() {
+        draw_instance_tabs(ui, tabs);
+    }
 
     if let None = snapshot {
         return;
@@ -166,183 +1594,386 @@ fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
     // Snapshot should always be present after this point.
     let snapshot = snapshot.unwrap();
 
-    if let Some(players_window) = players_window {
-        let p = &snapshot.player_globals;
-        ui.text_colored(ORANGE, format!("Respawn Failure: {}",p.respawn_failure));
-        ui.text_colored(ORANGE, format!("Are All Dead: {}", p.are_all_dead));
-        ui.text_colored(ORANGE, format!("Input Disabled: {}", p.input_disabled));
-        ui.text_colored(ORANGE, format!("Teleported: {}", p.teleported));
+    if settings.show_players {
+        let players_window = ui.window("Players Globals")
+            .size([SIDE_PANEL_WIDTH, side_panel_height], Condition::FirstUseEver)
+            .position([width - SIDE_PANEL_WIDTH, 20.0], Condition::FirstUseEver)
+            .opened(&mut settings.show_players)
+            .begin();
+
+        if let Some(players_window) = players_window {
+            let p = &snapshot.player_globals;
+            ui.text_colored(ORANGE, format!("Respawn Failure: {}",p.respawn_failure));
+            ui.text_colored(ORANGE, format!("Are All Dead: {}", p.are_all_dead));
+            ui.text_colored(ORANGE, format!("Input Disabled: {}", p.input_disabled));
+            ui.text_colored(ORANGE, format!("Teleported: {}", p.teleported));
+
+            for player in &snapshot.player_globals.local_players {
+                if !player.is_invald() {
+                    let player_index = player.get_index();
+                    ui.text(format!("-------------- Player {} --------------", player_index));
+                    print_player(ui, &snapshot, player_index);
+                }
+            }
+
+            players_window.end();
+        }
+    }
+
+    if settings.show_inspector {
+        let mut show_inspector = true;
+        let inspector_window = ui.window("Inspector")
+            .size([SIDE_PANEL_WIDTH, side_panel_height], Condition::FirstUseEver)
+            .position([width - SIDE_PANEL_WIDTH, 20.0 + side_panel_height], Condition::FirstUseEver)
+            .opened(&mut show_inspector)
+            .begin();
+
+        if let Some(inspector_window) = inspector_window {
+            if ui.collapsing_header("Target Flags", TreeNodeFlags::empty()) {
+                draw_object_flags(ui, settings, instance, snapshot);
+            }
+
+            // Recordings are never written to.
+            if instance.playback.is_none() {
+                if ui.collapsing_header(format!("Frozen Values ({})###FrozenValues", instance.freezes.values.len()), TreeNodeFlags::empty()) {
+                    draw_freezes(ui, settings, instance, snapshot);
+                }
 
-        for player in &snapshot.player_globals.local_players {
-            if !player.is_invald() {
-                let player_index = player.get_index();
-                ui.text(format!("-------------- Player {} --------------", player_index));
-                print_player(ui, &snapshot, player_index);
+                if ui.collapsing_header(format!("Write Journal ({})###WriteJournal", instance.journal.position()), TreeNodeFlags::empty()) {
+                    draw_journal(ui, settings, instance, snapshot.game_tick);
+                }
             }
+
+            inspector_window.end();
+        }
+
+        settings.show_inspector = show_inspector;
+    }
+
+    if settings.show_watchlist {
+        let mut show_watchlist = true;
+        let watchlist_window = ui.window("Watchlist")
+            .size([SIDE_PANEL_WIDTH, side_panel_height], Condition::FirstUseEver)
+            .position([width - SIDE_PANEL_WIDTH, 20.0 + side_panel_height * 2.0], Condition::FirstUseEver)
+            .opened(&mut show_watchlist)
+            .begin();
+
+        if let Some(watchlist_window) = watchlist_window {
+            draw_watchlist(ui, settings, instance, snapshot);
+            watchlist_window.end();
+        }
+
+        settings.show_watchlist = show_watchlist;
+    }
+
+    if settings.show_alerts {
+        let mut show_alerts = true;
+        let alerts_window = ui.window("Alerts")
+            .size([SIDE_PANEL_WIDTH, side_panel_height], Condition::FirstUseEver)
+            .position([width - SIDE_PANEL_WIDTH, 20.0 + side_panel_height * 3.0], Condition::FirstUseEver)
+            .opened(&mut show_alerts)
+            .begin();
+
+        if let Some(alerts_window) = alerts_window {
+            draw_alert_rules(ui, settings, instance);
+            alerts_window.end();
         }
 
-        players_window.end();
+        settings.show_alerts = show_alerts;
+    }
+
+    if settings.show_logs {
+        let logs_window = ui.window("Logs")
+            .size([SIDE_PANEL_WIDTH, side_panel_height], Condition::FirstUseEver)
+            .position([width - SIDE_PANEL_WIDTH, 20.0 + side_panel_height * 4.0], Condition::FirstUseEver)
+            .opened(&mut settings.show_logs)
+            .begin();
+
+        if let Some(logs_window) = logs_window {
+            if ui.collapsing_header(format!("Garbage Collection ({})###GarbageCollection", instance.gc_log.events.len()), TreeNodeFlags::empty()) {
+                for (event_index, event) in instance.gc_log.events.iter().enumerate().rev() {
+                    let color = ui.push_style_color(StyleColor::Text, if event.freed_player_datum() { RED } else { ORANGE });
+                    let node = ui.tree_node_config(format!("[{}] Collected {} objects##gc{}", format_event_time(event.timestamp, event.tick), event.objects.len(), event_index)).push();
+                    color.pop();
+
+                    if let Some(node) = node {
+                        if event.freed_player_datum() {
+                            ui.text_colored(RED, "Freed a player referenced datum!");
+                        }
+
+                        for object in &event.objects {
+                            ui.text_colored(
+                                if object.player_referenced { RED } else { WHITE },
+                                format!("{:<5} {:<5} {}", object.datum.get_index(), object.datum.get_id(), object.tag_path)
+                            );
+                        }
+
+                        node.pop();
+                    }
+                }
+            }
+
+            if ui.collapsing_header(format!("Alerts ({})###AlertLog", instance.alert_log.alerts.len()), TreeNodeFlags::empty()) {
+                for alert in instance.alert_log.alerts.iter().rev() {
+                    ui.text_colored(ORANGE, format!("[{}] {}:", format_event_time(alert.timestamp, alert.tick), alert.rule));
+                    ui.same_line();
+                    ui.text(&alert.message);
+                }
+            }
+
+            if ui.collapsing_header("Slot History", TreeNodeFlags::empty()) {
+                draw_slot_history(ui, instance);
+            }
+
+            logs_window.end();
+        }
     }
 
     if let Some(main_window) = main_window {
-        if let Some(table) = ui.begin_table_with_flags("ObjectsTable", 10, TableFlags::SIZING_STRETCH_PROP) {
-            ui.table_setup_column("");
-            ui.table_setup_column("Datum");
-            ui.table_setup_column("Index");
-            ui.table_setup_column("ID");
-            ui.table_setup_column("Player");
-            ui.table_setup_column("Coordinates");
-            // ui.table_setup_column("Flags");
-            ui.table_setup_column("Tag Name");
-            ui.table_setup_column("Tag Class");
-            ui.table_setup_column("Tag Class Secondary");
-            ui.table_setup_column("Tag Class Tertiary");
+        let matcher = draw_object_filter(ui, settings, snapshot);
+
+        // Slots the filter lets through, sorted once the table knows its sort column.
+        let mut visible_rows: Vec<(usize, Option<ObjectRow>)> = (0..snapshot.object_pool_entries.len())
+            .map(|index| (index, object_row(snapshot, index)))
+            .filter(|(index, row)| matcher.as_ref().is_none_or(|matcher| matcher.matches(*index, row.as_ref())))
+            .collect();
+
+        if settings.object_filter.is_active() {
+            ui.text_colored(ORANGE, format!("Showing {} of {} slots", visible_rows.len(), snapshot.object_pool_entries.len()));
+        }
+
+        let table_flags = TableFlags::SIZING_STRETCH_PROP | TableFlags::SORTABLE | TableFlags::REORDERABLE | TableFlags::HIDEABLE | TableFlags::RESIZABLE;
+        if let Some(table) = ui.begin_table_with_flags("ObjectsTable", OBJECT_COLUMNS.len(), table_flags) {
+            for column in OBJECT_COLUMNS {
+                let mut setup = TableColumnSetup::new(column.name());
+                setup.flags = column.flags();
+                ui.table_setup_column_with(setup);
+            }
             ui.table_headers_row();
 
-            for index in (0..=snapshot.object_pool_entries.len() as usize).rev() {
-                let identity = ui.push_id_usize(index);
+            if let Some(sort_specs) = ui.table_sort_specs_mut() {
+                sort_specs.conditional_sort(|specs| {
+                    settings.object_sort = specs.iter().next().map(|spec| (OBJECT_COLUMNS[spec.column_idx()], spec.sort_direction() == Some(TableSortDirection::Descending)));
+                });
+            }
+
+            if let Some((column, descending)) = settings.object_sort {
+                let origin = player_position(snapshot, 0);
+                visible_rows.sort_by(|first, second| {
+                    let ordering = compare_object_rows(column, instance, snapshot, origin, first, second);
+                    if descending { ordering.reverse() } else { ordering }
+                });
+            }
 
-                if snapshot.object_pool_entries.get(index).is_some() && 
-                   snapshot.game_object_entries.get(index).is_some()
-                {
-                    ui.table_next_row();
+            // Only the rows scrolled into view are submitted, every row has the same height.
+            let mut clipper = ListClipper::new(visible_rows.len() as i32).begin(ui);
+            while clipper.step() {
+                for (index, row) in &visible_rows[clipper.display_start() as usize..clipper.display_end() as usize] {
+                    let index = *index;
+                    let identity = ui.push_id_usize(index);
 
                     if snapshot.object_pool_entries.get(index).is_some() && 
-                        snapshot.game_object_entries.get(index).is_some() && 
-                        snapshot.object_pool_entries[index].as_ref().is_some() && 
-                        snapshot.game_object_entries[index].as_ref().is_some() 
+                       snapshot.game_object_entries.get(index).is_some()
                     {
-                        let object_pool_entry = snapshot.object_pool_entries[index].as_ref().unwrap();
-                        let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+                        ui.table_next_row();
+
+                        if snapshot.object_pool_entries.get(index).is_some() && 
+                            snapshot.game_object_entries.get(index).is_some() && 
+                            snapshot.object_pool_entries[index].as_ref().is_some() && 
+                            snapshot.game_object_entries[index].as_ref().is_some() 
+                        {
+                            let object_pool_entry = snapshot.object_pool_entries[index].as_ref().unwrap();
+                            let game_object_entry = snapshot.game_object_entries[index].as_ref().unwrap();
+                            let row = row.as_ref().unwrap();
     
-                        let datum_handle = DatumHandle::new_from_index_id(index as u16, object_pool_entry.id);
-
-                        ui.table_set_column_index(0);
-
-                        if ui.button(&"Set") {
-                            draw_context.target_index = index as u32;
-                        }
+                            let datum_handle = row.datum.clone();
+
+                            // Recorded snapshots can not be edited.
+                            let editable = instance.memory.is_some() && !settings.read_only;
+                            let position_freeze = instance.freezes.find_object(&datum_handle, FreezeField::Position);
+
+                            ui.table_set_column_index(0);
+
+                            if ui.button(&"Set") {
+                                instance.target_index = index as u32;
+                            }
+                            if ui.is_item_clicked_with_button(MouseButton::Right) {
+                                ui.open_popup("WatchObject");
+                            }
+                            if let Some(popup) = ui.begin_popup("WatchObject") {
+                                if ui.menu_item("Watch Datum") {
+                                    add_watch(settings, snapshot, format!("{} {}", datum_handle.get_handle(), row.tag_name), WatchTarget::Datum(datum_handle.clone()));
+                                }
+                                if ui.menu_item("Watch Slot") {
+                                    add_watch(settings, snapshot, format!("Slot {}", index), WatchTarget::Slot(index as u16));
+                                }
+                                popup.end();
+                            }
+
+                            ui.same_line();
+                            let mut frozen = position_freeze.is_some();
+                            ui.disabled(!editable, || {
+                                if ui.checkbox("##FreezePosition", &mut frozen) {
+                                    match position_freeze {
+                                        Some(freeze_index) => instance.freezes.remove(freeze_index),
+                                        None => {
+                                            let target = FreezeTarget::Object { datum: datum_handle.clone(), field: FreezeField::Position };
+                                            let bytes = game_object_entry.position.iter().flat_map(|value| value.to_le_bytes()).collect();
+                                            instance.freezes.add(format!("{} {} position", datum_handle.get_handle(), row.tag_name), target, bytes);
+                                        }
+                                    }
+                                }
+                            });
+                            if ui.is_item_hovered() {
+                                ui.tooltip_text("Freeze position");
+                            }
                         
-                        if index == draw_context.target_index as usize {
-                            ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
-                        }
+                            if index == instance.target_index as usize {
+                                ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
+                            }
 
-                        ui.table_next_column();
-                        ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", datum_handle.get_handle()));
+                            ui.table_next_column();
+                            ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", datum_handle.get_handle()));
 
-                        ui.table_next_column();
-                        ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", index));
+                            ui.table_next_column();
+                            ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", index));
     
-                        ui.table_next_column();
-                        ui.text_colored(
-                            if object_pool_entry.id == snapshot.object_pool_header.next_object_id { ORANGE } else { WHITE }, 
-                            format!("{:<5}", object_pool_entry.id )
-                        );
+                            ui.table_next_column();
+                            ui.text_colored(
+                                if object_pool_entry.id == snapshot.object_pool_header.next_object_id { ORANGE } else { WHITE }, 
+                                format!("{:<5}", object_pool_entry.id )
+                            );
     
-                        ui.table_next_column();
-                        if let Some(player_index) = snapshot.find_local_player_index_from_unit_index(index as u16) {
-                            ui.text_colored(GREEN, format!("{}", player_index))
-                        } else if let Some(local_dead_player_index) = snapshot.find_next_object_datum_player(datum_handle.clone()) {
-                            ui.text_colored(RED, format!("{}", local_dead_player_index));
-                        } else {
-                            ui.text("");
-                        }
-
-                        ui.table_next_column();
-                        let mut updated_position = game_object_entry.position.clone();
-
-                        if ui.input_float3(&"POS", &mut updated_position).build() {
-                            let manager = draw_context.memory.as_mut().unwrap();
-                            let base_ptr = u32::from_le_bytes([object_pool_entry.object_address[0], object_pool_entry.object_address[1], object_pool_entry.object_address[2], 0x0]);
-                            let game_object_pointer = base_ptr as usize - 0x18;
-
-                            manager.write((game_object_pointer + 0x24) as usize, &updated_position.get(0).as_ref().expect("Could not write X position").to_le_bytes());
-                            manager.write((game_object_pointer + 0x24 + 0x4) as usize, &updated_position.get(1).as_ref().expect("Could not write Y position").to_le_bytes());
-                            manager.write((game_object_pointer + 0x24 + 0x4 + 0x4) as usize, &updated_position.get(2).as_ref().expect("Could not write Z position").to_le_bytes());
-                        }
+                            ui.table_next_column();
+                            if let Some(player_index) = row.player {
+                                ui.text_colored(GREEN, format!("{}", player_index))
+                            } else if let Some(local_dead_player_index) = row.dead_player {
+                                ui.text_colored(RED, format!("{}", local_dead_player_index));
+                            } else {
+                                ui.text("");
+                            }
+
+                            ui.table_next_column();
+                            let mut updated_position = game_object_entry.position.clone();
+
+                            if ui.input_float3(&"POS", &mut updated_position).read_only(!editable).build() && editable {
+                                let bytes: Vec<u8> = updated_position.iter().flat_map(|value| value.to_le_bytes()).collect();
+
+                                // A frozen position follows the edit instead of snapping back.
+                                if let Some(freeze_index) = position_freeze {
+                                    instance.freezes.values[freeze_index].bytes = bytes.clone();
+                                }
+
+                                let description = format!("{} {} position", datum_handle.get_handle(), row.tag_name);
+                                let result = instance.write_object(&datum_handle, GAME_OBJECT_POSITION_OFFSET, &bytes, description, snapshot.game_tick);
+                                instance.report_write(result);
+                            }
 
                         
-                        /*
-                        ui.table_next_column();
+                            ui.table_next_column();
+                            let spawn_flags = occupant_spawn_flags(instance, &datum_handle);
+                            let changed = spawn_flags.map_or(0, |spawn_flags| spawn_flags ^ game_object_entry.flags);
+                            ui.text_colored(if changed != 0 { ORANGE } else { WHITE }, format!("{:08X}", game_object_entry.flags));
+                            if ui.is_item_hovered() {
+                                let mut tooltip = set_flag_names(game_object_entry.flags).join("\n");
+                                if changed != 0 {
+                                    tooltip.push_str(&format!("\n\nChanged since spawn: {}", (0..OBJECT_FLAG_BITS).filter(|bit| is_flag_set(changed, *bit)).map(object_flag_name).collect::<Vec<_>>().join(", ")));
+                                }
+                                ui.tooltip_text(tooltip);
+                            }
+
+                            ui.table_next_column();
+                            ui.text(&row.tag_name);
+
+                            ui.table_next_column();        
+                            ui.text(&row.tag_class);
                         
-                        let mut flags = game_object_entry.flags.clone();
-                        if ui.input_scalar(format!("Flags{}", datum_handle.get_handle()), &mut flags).build() {
-                            let manager = draw_context.memory.as_mut().unwrap();
-                            let base_ptr = u32::from_le_bytes([object_pool_entry.object_address[0], object_pool_entry.object_address[1], object_pool_entry.object_address[2], 0x0]);
-                            let game_object_pointer = base_ptr as usize - 0x18;
+                            ui.table_next_column();        
+                            ui.text(&row.tag_class_secondary);
 
-                            manager.write((game_object_pointer + 0x1C) as usize, &flags.to_le_bytes());
+                            ui.table_next_column();        
+                            ui.text(&row.tag_class_tertiary);
 
-                        }
-                        */
+                            ui.table_next_column();
+                            match player_position(snapshot, 0) {
+                                Some(origin) => ui.text(format!("{:.2}", distance(origin, row.position))),
+                                None => ui.text("")
+                            }
+
+                            ui.table_next_column();
+                            ui.text(object_pool_entry.game_object_address().map(|address| format!("{:#X}", address)).unwrap_or_default());
 
-                        ui.table_next_column();
-                        ui.text(format!("{}", snapshot.tags.get(&game_object_entry.tag_index).unwrap_or(&"UNKNOWN".to_string())));
+                            ui.table_next_column();
+                            ui.text(format!("{:#X}", game_object_entry.tag_index));
 
-                        ui.table_next_column();        
-                        if let Some(entry) = snapshot.tag_entries.get(&game_object_entry.tag_index) {
-                            let tag_class = String::from_utf8_lossy(&entry.tag_class).chars().rev().collect::<String>();
-                            ui.text(format!("{}", tag_class));
+                            ui.table_next_column();
+                            ui.text(occupant_spawned(instance, &datum_handle).map(format_timestamp).unwrap_or_default());
+    
                         } else {
+                            ui.table_set_column_index(0);
+
+                            if ui.button(&"Set") {
+                                instance.target_index = index as u32;
+                            }
+                            if ui.is_item_clicked_with_button(MouseButton::Right) {
+                                ui.open_popup("WatchObject");
+                            }
+                            if let Some(popup) = ui.begin_popup("WatchObject") {
+                                if ui.menu_item("Watch Slot") {
+                                    add_watch(settings, snapshot, format!("Slot {}", index), WatchTarget::Slot(index as u16));
+                                }
+                                popup.end();
+                            }
+
+                            if index == instance.target_index as usize {
+                                ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
+                            }
+
+                            ui.table_next_column();
                             ui.text("");
-                        }
-                        
-                        ui.table_next_column();        
-                        if let Some(entry) = snapshot.tag_entries.get(&game_object_entry.tag_index) {
-                            let tag_class = String::from_utf8_lossy(&entry.tag_class_secondary).chars().rev().collect::<String>();
-                            ui.text(format!("{}", tag_class));
-                        } else {
+
+                            ui.table_next_column();
+                            ui.text_colored(if first_free_index as usize == index { ORANGE } else { RED }, format!("{}", index));
+    
+                            ui.table_next_column();
+                            ui.text("Free");
+
+                            ui.table_next_column();
                             ui.text("");
-                        }
 
-                        ui.table_next_column();        
-                        if let Some(entry) = snapshot.tag_entries.get(&game_object_entry.tag_index) {
-                            let tag_class = String::from_utf8_lossy(&entry.tag_class_tertiary).chars().rev().collect::<String>();
-                            ui.text(format!("{}", tag_class));
-                        } else {
+                            ui.table_next_column();
                             ui.text("");
-                        }
-    
-                    } else {
-                        ui.table_set_column_index(0);
 
-                        if ui.button(&"Set") {
-                            draw_context.target_index = index as u32;
-                        }
+                            ui.table_next_column();
+                            ui.text("");
 
-                        if index == draw_context.target_index as usize {
-                            ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
-                        }
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text_colored(if first_free_index as usize == index { ORANGE } else { RED }, format!("{}", index));
-    
-                        ui.table_next_column();
-                        ui.text("Free");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
 
-                        ui.table_next_column();
-                        ui.text("");
+                            ui.table_next_column();
+                            ui.text("");
+                        }
 
-                        ui.table_next_column();
-                        ui.text("");
+                        identity.pop();
                     }
-
-                    identity.pop();
                 }
             }
 
@@ -354,6 +1985,65 @@ fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
 }
 
 
+fn add_instance(draw_context: &mut DrawContext, mut instance: Instance) {
+    instance.target_index = draw_context.settings.target_index;
+    draw_context.instances.push(instance);
+    draw_context.active_instance = draw_context.instances.len() - 1;
+    draw_context.select_instance = Some(draw_context.active_instance);
+    draw_context.show_attach = false;
+}
+
+fn attach(draw_context: &mut DrawContext, virtual_address: usize, pid: u32) {
+    let settings = &draw_context.settings;
+    let instance = Instance::attach(&settings.process_name, virtual_address, pid, &settings.profile, settings.sample_settings(), settings.read_only);
+    add_instance(draw_context, instance);
+}
+
+fn open_playback(draw_context: &mut DrawContext, path: &Path) {
+    match Instance::open_playback(path, &draw_context.settings.profile) {
+        Ok(instance) => {
+            draw_context.settings.playback_error = None;
+            add_instance(draw_context, instance);
+        }
+        Err(e) => draw_context.settings.playback_error = Some(e)
+    }
+}
+
+fn draw_process_table(ui: &Ui, draw_context: &mut DrawContext, processes: &[ProcessInfo]) {
+    if let Some(table) = ui.begin_table_with_flags("ProcessTable", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::BORDERS_INNER_H) {
+        ui.table_setup_column("PID");
+        ui.table_setup_column("Window Title");
+        ui.table_setup_column("Command Line");
+        ui.table_setup_column("");
+        ui.table_headers_row();
+
+        for process in processes {
+            let attached = draw_context.instances.iter().any(|instance| instance.pid() == Some(process.pid));
+
+            ui.table_next_row();
+            ui.table_set_column_index(0);
+
+            let selected = draw_context.settings.selected_pid == Some(process.pid);
+            if ui.selectable_config(format!("{}", process.pid)).selected(selected).span_all_columns(true).build() {
+                draw_context.settings.selected_pid = Some(process.pid);
+            }
+
+            ui.table_next_column();
+            ui.text(&process.window_title);
+
+            ui.table_next_column();
+            ui.text(&process.command_line);
+
+            ui.table_next_column();
+            if attached {
+                ui.text_colored(GREEN, "Attached");
+            }
+        }
+
+        table.end();
+    }
+}
+
 fn draw_attach(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
     ui.main_menu_bar(|| {
         if let Some(token) = ui.begin_menu("File") {
@@ -377,35 +2067,72 @@ fn draw_attach(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawConte
 
     if let Some(attach_window) = attach_window {
 
-        let sys = System::new_all();
-        let processes: Vec<_> = sys.processes_by_exact_name(OsStr::new("xemu.exe")).collect();
+        let preview = draw_context.settings.profile.description;
+        if let Some(combo) = ui.begin_combo("Game Profile", preview) {
+            for profile in GAME_PROFILES {
+                if ui.selectable(profile.description) {
+                    draw_context.settings.profile = profile.clone();
+                }
+            }
+            combo.end();
+        }
 
-        if processes.len() == 0 {
-            ui.text("Could not find running instance of xemu.exe");
-        } else if processes.len() > 1 {
-            ui.text("Found multiple instances of xemu.exe running on the system. Please only have one instance running.");
-        } else if processes.len() == 1 {
-            ui.text("Found xemu.exe");
+        ui.separator();
 
-            let process = processes[0];
+        let process_name = draw_context.settings.process_name.clone();
+        let processes = find_processes(&process_name);
+
+        // Default to the only process, otherwise make the user pick one.
+        if processes.len() == 1 {
+            draw_context.settings.selected_pid = Some(processes[0].pid);
+        } else if !processes.iter().any(|process| Some(process.pid) == draw_context.settings.selected_pid) {
+            draw_context.settings.selected_pid = None;
+        }
+
+        if processes.len() == 0 {
+            ui.text(format!("Could not find running instance of {}", process_name));
+        } else {
+            ui.text(format!("Found {} instance(s) of {}, select the one to attach to.", processes.len(), process_name));
+            draw_process_table(ui, draw_context, &processes);
 
-            ui.text(r#"Run (gpa2hva 0x0) in xemu.exe and put the result below."#);
+            ui.text(format!("Run (gpa2hva 0x0) in the selected {} and put the result below.", process_name));
             
-            ui.input_text("Virtual Address to Physical Xbox Memory", &mut draw_context.virtual_address)
+            ui.input_text("Virtual Address to Physical Xbox Memory", &mut draw_context.settings.virtual_address)
                 .allow_tab_input(false)
                 .chars_hexadecimal(true)
                 .chars_noblank(true)
                 .build();
 
-            if ui.button("Set Virtual Address") {
-                if let Ok(value) = usize::from_str_radix(&draw_context.virtual_address, 16) {
-                    draw_context.memory = Some(
-                        ProcessMemory::new(value, 67108864, process.pid().as_u32())                        
-                    );
+            if let Some(pid) = draw_context.settings.selected_pid {
+                if ui.button("Set Virtual Address") {
+                    if let Ok(value) = usize::from_str_radix(&draw_context.settings.virtual_address, 16) {
+                        attach(draw_context, value, pid);
+                    }
                 }
             }
+        }
 
-    
+        ui.separator();
+        ui.text("Or open a saved snapshot, a RAM dump, a recorded session, or a folder of them to play back without an emulator.");
+
+        ui.input_text("Snapshot, RAM Dump or Session Path", &mut draw_context.settings.playback_path)
+            .allow_tab_input(false)
+            .build();
+
+        if ui.button("Open") {
+            let path = PathBuf::from(&draw_context.settings.playback_path);
+            open_playback(draw_context, &path);
+        }
+
+        if let Some(error) = &draw_context.settings.playback_error {
+            ui.text_colored(RED, error);
+        }
+
+        if !draw_context.instances.is_empty() {
+            ui.separator();
+            if ui.button("Cancel") {
+                draw_context.show_attach = false;
+            }
         }
 
         attach_window.end();
@@ -413,14 +2140,85 @@ fn draw_attach(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawConte
 
 }
 
-fn start() {
+fn start(options: &Options, config: &Config) {
     // Setup draw context
     let mut draw_context = DrawContext {
-        virtual_address: String::default(),
-        memory: None,
-        target_index: 0
+        instances: Vec::new(),
+        active_instance: 0,
+        select_instance: None,
+        show_attach: false,
+        settings: Settings {
+            process_name: options.process.clone(),
+            virtual_address: options.address.map(|address| format!("{:X}", address)).unwrap_or(config.virtual_address.clone()),
+            selected_pid: options.pid,
+            playback_path: String::default(),
+            playback_error: None,
+            journal_path: config.journal_path.clone(),
+            freeze_kind: 0,
+            freeze_offset: String::default(),
+            freeze_value: String::default(),
+            freeze_error: None,
+            watchlists: load_watchlists(&config_path(WATCHLISTS_FILE)),
+            watch_kind: 0,
+            watch_label: String::default(),
+            watch_player: 0,
+            watch_player_field: 0,
+            watch_address: String::default(),
+            watch_value_kind: 2,
+            watch_error: None,
+            alert_rules: Vec::new(),
+            alert_name: String::default(),
+            alert_condition: 0,
+            alert_slot: -1,
+            alert_tag_path: String::default(),
+            alert_value: 0,
+            alert_actions: AlertActions::default(),
+            alert_write_address: String::default(),
+            alert_write_bytes: String::default(),
+            alert_error: None,
+            object_filter: ObjectFilter::default(),
+            filter_presets: load_filter_presets(&config_path(FILTER_PRESETS_FILE)),
+            filter_preset_name: String::default(),
+            filter_regex: RegexCache::new(),
+            object_sort: Some((ObjectColumn::Index, true)),
+            target_index: config.target_index,
+            theme: config.theme,
+            show_players: config.show_players,
+            show_inspector: config.show_inspector,
+            show_watchlist: config.show_watchlist,
+            show_alerts: config.show_alerts,
+            show_logs: config.show_logs,
+            layout_presets: load_layout_presets(&config_path(LAYOUT_PRESETS_FILE)),
+            layout_name: String::default(),
+            layout_request: None,
+            layout_error: None,
+            profile: options.profile.clone(),
+            read_only: options.read_only,
+            sample_rate: options.sample_rate,
+            tick_sync: options.tick_sync,
+            suspend_while_reading: options.suspend_while_reading
+        }
     };
 
+    match load_alert_rules(&config_path(ALERT_RULES_FILE)) {
+        Ok(rules) => draw_context.settings.alert_rules = rules,
+        Err(e) => {
+            println!("{}", e);
+            draw_context.settings.alert_error = Some(e);
+        }
+    }
+
+    // Skip the attach screen when the command line says what to open.
+    if let Some(path) = &options.dump {
+        draw_context.settings.playback_path = path.display().to_string();
+        open_playback(&mut draw_context, path);
+    } else if let Some(address) = options.address {
+        match options.pid.map_or_else(|| find_single_process(&options.process), Ok) {
+            Ok(pid) => attach(&mut draw_context, address, pid),
+            Err(e) => println!("{}", e)
+        }
+    }
+
     /* */
     /* initialize SDL and its video subsystem */
     let sdl = sdl2::init().unwrap();
@@ -433,10 +2231,14 @@ fn start() {
     gl_attr.set_context_profile(GLProfile::Core);
 
     /* create a new window, be sure to call opengl method on the builder when using glow! */
-    let window = video_subsystem
-        .window("Halo Object Viewer", 1600, 900)
+    let mut window_builder = video_subsystem.window("Halo Object Viewer", config.window_size[0].max(640), config.window_size[1].max(480));
+    match config.window_position {
+        Some([x, y]) => window_builder.position(x, y),
+        None => window_builder.position_centered()
+    };
+
+    let window = window_builder
         .opengl()
-        .position_centered()
         .resizable()
         .build()
         .unwrap();
@@ -454,9 +2256,16 @@ fn start() {
     /* create context */
     let mut imgui = Context::create();
 
-    /* disable creation of files on disc */
-    imgui.set_ini_filename(None);
+    /* keep the window and table layout between runs */
+    let layout_path = config_path(LAYOUT_FILE);
+    if let Some(dir) = layout_path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            println!("Could not create {}: {}", dir.display(), e);
+        }
+    }
+    imgui.set_ini_filename(Some(layout_path));
     imgui.set_log_filename(None);
+    imgui.io_mut().config_flags |= ConfigFlags::DOCKING_ENABLE;
 
     /* setup platform and renderer, and fonts to imgui */
     imgui
@@ -469,6 +2278,7 @@ fn start() {
 
     /* start main loop */
     let mut event_pump = sdl.event_pump().unwrap();
+    let mut applied_theme = None;
 
     'main: loop {
         for event in event_pump.poll_iter() {
@@ -480,6 +2290,12 @@ fn start() {
             }
         }
 
+        /* styles can only change between frames */
+        if applied_theme != Some(draw_context.settings.theme) {
+            draw_context.settings.theme.apply(imgui.style_mut());
+            applied_theme = Some(draw_context.settings.theme);
+        }
+
         /* call prepare_frame before calling imgui.new_frame() */
         platform.prepare_frame(&mut imgui, &window, &event_pump);
 
@@ -487,8 +2303,10 @@ fn start() {
         let mut should_exit = false;
         
         /* create imgui UI here */
-        
-        if draw_context.memory.is_some() {
+
+        update_instances(ui, &mut draw_context);
+
+        if !draw_context.instances.is_empty() && !draw_context.show_attach {
             draw(ui, &mut should_exit, &mut draw_context);
         } else {
             draw_attach(ui, &mut should_exit, &mut draw_context);
@@ -505,9 +2323,51 @@ fn start() {
         renderer.render(draw_data).unwrap();
 
         window.gl_swap_window();
+
+        /* layouts are saved and loaded outside of a frame */
+        if let Some(request) = draw_context.settings.layout_request.take() {
+            let settings = &mut draw_context.settings;
+            let result = handle_layout_request(&mut imgui, &mut settings.layout_presets, request)
+                .and_then(|changed| if changed { save_layout_presets(&config_path(LAYOUT_PRESETS_FILE), &settings.layout_presets) } else { Ok(()) });
+            settings.layout_error = result.err();
+        }
+    }
+
+    /* remember the settings for the next run */
+    if let Some(instance) = draw_context.instances.get(draw_context.active_instance) {
+        draw_context.settings.target_index = instance.target_index;
+    }
+
+    let (width, height) = window.size();
+    let (x, y) = window.position();
+    if let Err(e) = save_config(&draw_context.settings.config([width, height], [x, y])) {
+        println!("{}", e);
     }
 }
 
 fn main() {
-    start();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = load_config();
+
+    // Scripts running headless should not depend on what was last picked in the viewer.
+    let defaults = if args.iter().any(|arg| arg == "--headless") { Options::default() } else { config.options() };
+
+    let options = match cli::parse_args(args, defaults) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if options.help {
+        println!("{}", cli::USAGE);
+    } else if options.headless {
+        if let Err(e) = headless::run(&options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    } else {
+        start(&options, &config);
+    }
 }
@@ -1,56 +1,387 @@
-use std::ffi::c_void;
+use std::{collections::HashMap, ffi::{c_void, OsStr}, time::{Duration, Instant}};
 
-use windows::Win32::{Foundation::HANDLE, System::Threading::{PROCESS_ALL_ACCESS}};
+use sysinfo::System;
+use windows::Win32::{
+    Foundation::{CloseHandle, BOOL, HANDLE, HWND, LPARAM, STILL_ACTIVE, TRUE},
+    System::{
+        Diagnostics::{
+            Debug::ReadProcessMemory,
+            ToolHelp::{CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32}
+        },
+        Memory::{VirtualQueryEx, MEMORY_BASIC_INFORMATION, MEM_COMMIT},
+        Threading::{
+            GetExitCodeProcess, OpenProcess, OpenThread, ResumeThread, SuspendThread, PROCESS_QUERY_INFORMATION, PROCESS_VM_OPERATION,
+            PROCESS_VM_READ, PROCESS_VM_WRITE, THREAD_SUSPEND_RESUME
+        }
+    },
+    UI::WindowsAndMessaging::{EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible}
+};
+
+// Retail Xbox guest RAM
+pub const XBOX_RAM_SIZE: usize = 67108864;
+
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub command_line: String,
+    pub window_title: String
+}
+
+// Title of the first visible window owned by each process.
+fn window_titles() -> HashMap<u32, String> {
+    unsafe extern "system" fn collect_title(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let titles = &mut *(lparam.0 as *mut HashMap<u32, String>);
+
+        if IsWindowVisible(hwnd).as_bool() {
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+            let mut buffer = [0u16; 256];
+            let length = GetWindowTextW(hwnd, &mut buffer);
+            if length > 0 {
+                titles.entry(pid).or_insert_with(|| String::from_utf16_lossy(&buffer[..length as usize]));
+            }
+        }
+
+        TRUE
+    }
+
+    let mut titles: HashMap<u32, String> = HashMap::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect_title), LPARAM(&mut titles as *mut _ as isize));
+    }
+
+    titles
+}
+
+pub fn find_processes(name: &str) -> Vec<ProcessInfo> {
+    let sys = System::new_all();
+    let titles = window_titles();
+
+    let mut processes: Vec<_> = sys.processes_by_exact_name(OsStr::new(name))
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            command_line: process.cmd().iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" "),
+            window_title: titles.get(&process.pid().as_u32()).cloned().unwrap_or_default()
+        })
+        .collect();
+
+    processes.sort_by_key(|process| process.pid);
+    processes
+}
+
+pub fn find_single_process(name: &str) -> Result<u32, String> {
+    let processes = find_processes(name);
+
+    match processes.len() {
+        0 => Err(format!("Could not find running instance of {}", name)),
+        1 => Ok(processes[0].pid),
+        _ => Err(format!("Found multiple instances of {}, pick one with --pid.", name))
+    }
+}
+
+// Guest RAM is one allocation inside the emulator, find the one holding `signature` at `offset`.
+pub fn find_guest_ram(pid: u32, offset: usize, signature: u32) -> Option<usize> {
+    let handle = unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid).ok()? };
+
+    let mut found = None;
+    let mut address = 0usize;
+
+    loop {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let size = unsafe { VirtualQueryEx(handle, Some(address as *const c_void), &mut info, size_of::<MEMORY_BASIC_INFORMATION>()) };
+        if size == 0 || info.RegionSize == 0 {
+            break;
+        }
+
+        let base = info.BaseAddress as usize;
+        if info.State == MEM_COMMIT && base == info.AllocationBase as usize && info.RegionSize > offset + 4 {
+            let mut value = [0u8; 4];
+            let read = unsafe { ReadProcessMemory(handle, (base + offset) as *const c_void, value.as_mut_ptr() as *mut c_void, value.len(), None) };
+
+            if read.is_ok() && u32::from_le_bytes(value) == signature {
+                found = Some(base);
+                break;
+            }
+        }
+
+        address = base + info.RegionSize;
+    }
+
+    unsafe {
+        let _ = CloseHandle(handle);
+    }
+
+    found
+}
+
+// Guest RAM is read in pages, a snapshot only touches a few hundred of them.
+pub const PAGE_SIZE: usize = 0x1000;
+
+// Guest RAM addressed by physical address, either a live process or a full copy such as a RAM dump.
+pub trait GuestMemory {
+    // Fill `buffer` from guest RAM starting at `address`, false if any of it could not be read.
+    fn read_into(&mut self, address: usize, buffer: &mut [u8]) -> bool;
+}
+
+impl GuestMemory for &[u8] {
+    fn read_into(&mut self, address: usize, buffer: &mut [u8]) -> bool {
+        match self.get(address..address + buffer.len()) {
+            Some(bytes) => {
+                buffer.copy_from_slice(bytes);
+                true
+            }
+            None => false
+        }
+    }
+}
+
+// Reads that skip the page cache, for small values polled between snapshots such as the game tick.
+pub struct DirectMemory<'a>(pub &'a ProcessMemory);
+
+impl GuestMemory for DirectMemory<'_> {
+    fn read_into(&mut self, address: usize, buffer: &mut [u8]) -> bool {
+        if address + buffer.len() > XBOX_RAM_SIZE {
+            return false;
+        }
+
+        let result = unsafe {
+            ReadProcessMemory(
+                self.0.handle,
+                (self.0.virtual_address + address) as *const c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len(),
+                None
+            )
+        };
+
+        result.is_ok()
+    }
+}
+
+// Keeps every thread of the emulator suspended until dropped, so guest RAM can not change while it is read.
+pub struct SuspendedProcess {
+    threads: Vec<HANDLE>,
+    started: Instant
+}
+
+impl SuspendedProcess {
+    // Resume the emulator and return how long it was held.
+    pub fn resume(self) -> Duration {
+        let started = self.started;
+        drop(self);
+        started.elapsed()
+    }
+}
+
+impl Drop for SuspendedProcess {
+    fn drop(&mut self) {
+        for thread in &self.threads {
+            unsafe {
+                ResumeThread(*thread);
+                let _ = CloseHandle(*thread);
+            }
+        }
+    }
+}
 
 pub struct ProcessMemory {
-    bytes: Vec<u8>,
+    // Pages read since the last call to begin_sample.
+    pages: HashMap<usize, Box<[u8]>>,
+    bytes_read: usize,
     virtual_address: usize,
     pid: u32,
+    // Opened without write rights, every write is refused.
+    read_only: bool,
     handle: HANDLE
 }
 
 impl ProcessMemory {
-    pub fn new(virtual_address: usize, capacity: usize, pid: u32) -> ProcessMemory {
-        let handle = unsafe { windows::Win32::System::Threading::OpenProcess(PROCESS_ALL_ACCESS, false, pid)
-            .expect("Could not open process.") };
+    pub fn new(virtual_address: usize, pid: u32, read_only: bool) -> Result<ProcessMemory, String> {
+        let access = if read_only {
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ
+        } else {
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ | PROCESS_VM_WRITE | PROCESS_VM_OPERATION
+        };
 
-        ProcessMemory {
-            bytes: vec![0; capacity],
+        let handle = unsafe { OpenProcess(access, false, pid) }
+            .map_err(|e| format!("Could not open process {}: {}", pid, e))?;
+
+        Ok(ProcessMemory {
+            pages: HashMap::new(),
+            bytes_read: 0,
             pid: pid,
             virtual_address: virtual_address,
+            read_only: read_only,
             handle: handle
-        }
+        })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    pub fn virtual_address(&self) -> usize {
+        self.virtual_address
+    }
+
+    pub fn set_virtual_address(&mut self, virtual_address: usize) {
+        self.virtual_address = virtual_address;
+    }
+
+    // False once the emulator has exited, reads from the handle will only return stale bytes after that.
+    pub fn is_alive(&self) -> bool {
+        let mut exit_code = 0u32;
+        let result = unsafe { GetExitCodeProcess(self.handle, &mut exit_code) };
+        result.is_ok() && exit_code == STILL_ACTIVE.0 as u32
     }
 
-    pub fn read(&mut self) -> &[u8] {
+    pub fn suspend(&self) -> SuspendedProcess {
+        let started = Instant::now();
+        let mut threads = Vec::new();
+
         unsafe {
-            let _ = windows::Win32::System::Diagnostics::Debug::ReadProcessMemory(
-                self.handle, 
-                self.virtual_address as *const c_void, 
-                self.bytes.as_mut_ptr() as *mut c_void,
-                self.bytes.len(), 
+            if let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) {
+                let mut entry = THREADENTRY32 { dwSize: size_of::<THREADENTRY32>() as u32, ..Default::default() };
+                let mut next = Thread32First(snapshot, &mut entry);
+
+                while next.is_ok() {
+                    if entry.th32OwnerProcessID == self.pid {
+                        if let Ok(thread) = OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID) {
+                            if SuspendThread(thread) != u32::MAX {
+                                threads.push(thread);
+                            } else {
+                                let _ = CloseHandle(thread);
+                            }
+                        }
+                    }
+
+                    next = Thread32Next(snapshot, &mut entry);
+                }
+
+                let _ = CloseHandle(snapshot);
+            }
+        }
+
+        SuspendedProcess {
+            threads: threads,
+            started: started
+        }
+    }
+
+    // Forget the pages read for the previous snapshot, guest RAM has moved on since.
+    pub fn begin_sample(&mut self) {
+        self.pages.clear();
+        self.bytes_read = 0;
+    }
+
+    // Bytes copied out of the process since begin_sample.
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    // Read a run of pages that are not cached yet with a single call.
+    fn read_pages(&mut self, first_page: usize, page_count: usize) -> bool {
+        let mut bytes = vec![0u8; page_count * PAGE_SIZE];
+        let result = unsafe {
+            ReadProcessMemory(
+                self.handle,
+                (self.virtual_address + first_page * PAGE_SIZE) as *const c_void,
+                bytes.as_mut_ptr() as *mut c_void,
+                bytes.len(),
                 None
-            );
+            )
+        };
+
+        if result.is_err() {
+            return false;
         }
 
-        &self.bytes
+        self.bytes_read += bytes.len();
+        for (offset, page) in bytes.chunks_exact(PAGE_SIZE).enumerate() {
+            self.pages.insert(first_page + offset, page.into());
+        }
+
+        true
     }
 
-    pub fn write(&mut self, physical_address: usize, write_bytes: &[u8]) {
+    // False if the bytes could not be written.
+    pub fn write(&mut self, physical_address: usize, write_bytes: &[u8]) -> bool {
+        if self.read_only {
+            println!("Refused to write {} bytes at {:#X}, the process was opened read only.", write_bytes.len(), physical_address);
+            return false;
+        }
+
         let write_address = physical_address + self.virtual_address;
-        unsafe {
-            let res = windows::Win32::System::Diagnostics::Debug::WriteProcessMemory(
+        let res = unsafe {
+            windows::Win32::System::Diagnostics::Debug::WriteProcessMemory(
                 self.handle, 
                 write_address as *const c_void, 
                 write_bytes.as_ptr() as *mut c_void, 
                 write_bytes.len(), 
                 None
-            );
+            )
+        };
+
+        // Drop cached pages so the next read sees the write.
+        for page in physical_address / PAGE_SIZE..=(physical_address + write_bytes.len()) / PAGE_SIZE {
+            self.pages.remove(&page);
+        }
 
-            if let Err(e) = res {
-                println!("Res: {}", e);
+        if let Err(e) = res {
+            println!("Res: {}", e);
+            return false;
+        }
+
+        true
+    }
+}
+
+impl GuestMemory for ProcessMemory {
+    fn read_into(&mut self, address: usize, buffer: &mut [u8]) -> bool {
+        if buffer.is_empty() {
+            return true;
+        }
+
+        if address + buffer.len() > XBOX_RAM_SIZE {
+            return false;
+        }
+
+        let first_page = address / PAGE_SIZE;
+        let last_page = (address + buffer.len() - 1) / PAGE_SIZE;
+
+        // Fetch every missing run of pages first, then copy out of the cache.
+        let mut page = first_page;
+        while page <= last_page {
+            if self.pages.contains_key(&page) {
+                page += 1;
+                continue;
+            }
+
+            let run_start = page;
+            while page <= last_page && !self.pages.contains_key(&page) {
+                page += 1;
+            }
+
+            if !self.read_pages(run_start, page - run_start) {
+                return false;
             }
         }
+
+        let mut written = 0;
+        for page in first_page..=last_page {
+            let bytes = &self.pages[&page];
+            let start = if page == first_page { address % PAGE_SIZE } else { 0 };
+            let length = (PAGE_SIZE - start).min(buffer.len() - written);
+
+            buffer[written..written + length].copy_from_slice(&bytes[start..start + length]);
+            written += length;
+        }
+
+        true
     }
 }
 
@@ -58,7 +389,7 @@ impl Drop for ProcessMemory {
     fn drop(&mut self) {
         if !self.handle.is_invalid() {
             unsafe {
-                let _ = windows::Win32::Foundation::CloseHandle(self.handle);
+                let _ = CloseHandle(self.handle);
             }
         }
     }
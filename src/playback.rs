@@ -0,0 +1,473 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration
+};
+
+use crate::{
+    diff::{diff_snapshots, SnapshotEvent, DEFAULT_MOVE_THRESHOLD},
+    engine::{build_snapshot, DatumHandle, EngineSnapshot, GameProfile},
+    gc::GcLog,
+    history::SlotHistory,
+    memory::XBOX_RAM_SIZE,
+    recorder::{SessionCheckpoint, SessionReader, SESSION_EXTENSION},
+    serialization::{load_snapshot, SNAPSHOT_EXTENSION}
+};
+
+// Frames of a recorded session between two checkpoints, seeking decodes at most this many.
+const SESSION_CHECKPOINT_INTERVAL: usize = 256;
+
+pub enum FrameSource {
+    // A saved snapshot or RAM dump.
+    File(PathBuf),
+    // The nth frame of a recorded session.
+    Session { session: usize, frame: usize }
+}
+
+pub struct PlaybackFrame {
+    pub timestamp: Duration,
+    pub source: FrameSource
+}
+
+struct SessionFile {
+    path: PathBuf,
+    // One every SESSION_CHECKPOINT_INTERVAL frames, starting before the first.
+    checkpoints: Vec<SessionCheckpoint>
+}
+
+pub struct PlaybackMarker {
+    pub frame: usize,
+    pub label: String
+}
+
+// What the scan thread finds, in frame order.
+enum ScanMessage {
+    Frame(PlaybackFrame),
+    Marker(PlaybackMarker),
+    SessionStarted(PathBuf),
+    // Belongs to the session started last.
+    Checkpoint(SessionCheckpoint),
+    // Files fully read so far.
+    Progress(usize),
+    Done { gc_log: GcLog, slot_history: SlotHistory },
+    Error(String)
+}
+
+// Garbage collection log and slot history built from every frame, handed over once the scan is done.
+pub struct ScanResult {
+    pub gc_log: GcLog,
+    pub slot_history: SlotHistory
+}
+
+pub struct Playback {
+    pub path: PathBuf,
+    pub profile: GameProfile,
+    pub frames: Vec<PlaybackFrame>,
+    pub markers: Vec<PlaybackMarker>,
+    pub position: usize,
+    pub playing: bool,
+    // Files read by the scan thread out of all of them, the scan is over once it is None.
+    pub scan_progress: Option<(usize, usize)>,
+    pub scan_error: Option<String>,
+    scan: Option<Receiver<ScanMessage>>,
+    sessions: Vec<SessionFile>,
+    // Reader left after the last session frame was decoded, with the session and the frame it reads next.
+    reader: Option<(usize, usize, SessionReader)>,
+    clock: Duration,
+    loaded: Option<usize>
+}
+
+impl Playback {
+    // Opens a single saved snapshot, RAM dump or recorded session, or a folder of them ordered by file name.
+    // Every frame is decoded once on a separate thread to place the event markers and build the garbage collection log and slot history.
+    // Only the first frame is waited for, the rest show up on the timeline as they are read.
+    pub fn open(path: &Path, profile: &GameProfile) -> io::Result<Playback> {
+        let paths = playback_paths(path)?;
+        let file_count = paths.len();
+        let (sender, receiver) = mpsc::channel();
+        let scan_profile = profile.clone();
+
+        thread::spawn(move || {
+            let mut scan = Scan {
+                sender: sender,
+                frame_count: 0,
+                session_count: 0,
+                last_timestamp: Duration::ZERO,
+                previous: None,
+                gc_log: GcLog::new(),
+                slot_history: SlotHistory::new()
+            };
+
+            let message = match scan.run(paths, &scan_profile) {
+                Ok(()) => ScanMessage::Done { gc_log: scan.gc_log, slot_history: scan.slot_history },
+                Err(e) => ScanMessage::Error(e.to_string())
+            };
+
+            let _ = scan.sender.send(message);
+        });
+
+        let mut playback = Playback {
+            path: path.to_path_buf(),
+            profile: profile.clone(),
+            frames: Vec::new(),
+            markers: Vec::new(),
+            position: 0,
+            playing: false,
+            scan_progress: Some((0, file_count)),
+            scan_error: None,
+            scan: Some(receiver),
+            sessions: Vec::new(),
+            reader: None,
+            clock: Duration::ZERO,
+            loaded: None
+        };
+
+        // Everything up to the first frame, later frames are picked up by poll_scan.
+        while playback.frames.is_empty() {
+            let message = playback.scan.as_ref().and_then(|scan| scan.recv().ok());
+            match message {
+                Some(ScanMessage::Error(e)) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+                Some(ScanMessage::Done { .. }) | None => return Err(io::Error::new(io::ErrorKind::InvalidData, "No frames were recorded.")),
+                Some(message) => {
+                    playback.receive(message);
+                }
+            }
+        }
+
+        Ok(playback)
+    }
+
+    // Take in what the scan thread found since the last call, the result is returned once when the scan finishes.
+    pub fn poll_scan(&mut self) -> Option<ScanResult> {
+        let messages: Vec<_> = match &self.scan {
+            Some(scan) => scan.try_iter().collect(),
+            None => return None
+        };
+
+        let mut result = None;
+        for message in messages {
+            if let Some(scan_result) = self.receive(message) {
+                result = Some(scan_result);
+            }
+        }
+
+        result
+    }
+
+    fn receive(&mut self, message: ScanMessage) -> Option<ScanResult> {
+        match message {
+            ScanMessage::Frame(frame) => self.frames.push(frame),
+            ScanMessage::Marker(marker) => self.markers.push(marker),
+            ScanMessage::SessionStarted(path) => self.sessions.push(SessionFile { path: path, checkpoints: Vec::new() }),
+            ScanMessage::Checkpoint(checkpoint) => {
+                if let Some(session) = self.sessions.last_mut() {
+                    session.checkpoints.push(checkpoint);
+                }
+            }
+            ScanMessage::Progress(files) => {
+                if let Some((_, file_count)) = self.scan_progress {
+                    self.scan_progress = Some((files, file_count));
+                }
+            }
+            ScanMessage::Done { gc_log, slot_history } => {
+                self.scan = None;
+                self.scan_progress = None;
+                return Some(ScanResult { gc_log: gc_log, slot_history: slot_history });
+            }
+            ScanMessage::Error(e) => {
+                println!("Could not read all of {}: {}", self.path.display(), e);
+                self.scan = None;
+                self.scan_progress = None;
+                self.scan_error = Some(e);
+            }
+        }
+
+        None
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map(|frame| frame.timestamp).unwrap_or_default()
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        self.frames[self.position].timestamp
+    }
+
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.frames.len() - 1);
+        self.clock = self.timestamp();
+    }
+
+    pub fn step(&mut self, offset: isize) {
+        self.seek(self.position.saturating_add_signed(offset));
+    }
+
+    pub fn previous_marker(&self) -> Option<usize> {
+        self.markers.iter().rev().map(|marker| marker.frame).find(|frame| *frame < self.position)
+    }
+
+    pub fn next_marker(&self) -> Option<usize> {
+        self.markers.iter().map(|marker| marker.frame).find(|frame| *frame > self.position)
+    }
+
+    // Moves the playhead forward by the time elapsed since the last UI frame while playing.
+    pub fn advance(&mut self, elapsed: Duration) {
+        if !self.playing {
+            return;
+        }
+
+        self.clock += elapsed;
+        while self.position + 1 < self.frames.len() && self.frames[self.position + 1].timestamp <= self.clock {
+            self.position += 1;
+        }
+
+        // Frames still being scanned are waited for.
+        if self.position + 1 == self.frames.len() && self.scan.is_none() {
+            self.playing = false;
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded == Some(self.position)
+    }
+
+    // Where the frame came from, shown on the timeline.
+    pub fn frame_name(&self, index: usize) -> String {
+        match &self.frames[index].source {
+            FrameSource::File(path) => path.display().to_string(),
+            FrameSource::Session { session, frame } => format!("{} frame {}", self.sessions[*session].path.display(), frame + 1)
+        }
+    }
+
+    // Decodes the frame under the playhead.
+    pub fn load_snapshot(&mut self) -> Option<EngineSnapshot> {
+        self.loaded = Some(self.position);
+
+        let result = match &self.frames[self.position].source {
+            FrameSource::File(path) => read_frame(path, &self.profile),
+            FrameSource::Session { session, frame } => self.read_session_frame(*session, *frame)
+        };
+
+        match result {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                println!("Could not read {}: {}", self.frame_name(self.position), e);
+                None
+            }
+        }
+    }
+
+    // Sessions only store what changed, so frames are decoded forward from the nearest checkpoint.
+    // Playing forward keeps the reader from the last frame and never goes back to a checkpoint.
+    fn read_session_frame(&mut self, session: usize, frame: usize) -> io::Result<Option<EngineSnapshot>> {
+        let mut reader = match self.reader.take() {
+            Some((reader_session, next_frame, reader)) if reader_session == session && next_frame <= frame && frame - next_frame < SESSION_CHECKPOINT_INTERVAL => {
+                (next_frame, reader)
+            }
+            _ => {
+                let checkpoint = frame / SESSION_CHECKPOINT_INTERVAL;
+                let file = &self.sessions[session];
+                (checkpoint * SESSION_CHECKPOINT_INTERVAL, SessionReader::resume(&file.path, &file.checkpoints[checkpoint])?)
+            }
+        };
+
+        let mut snapshot = None;
+        while reader.0 <= frame {
+            snapshot = reader.1.next_frame()?.map(|(_, snapshot)| snapshot);
+            reader.0 += 1;
+        }
+
+        self.reader = Some((session, reader.0, reader.1));
+        Ok(snapshot)
+    }
+}
+
+// Goes through every frame in order on the scan thread, sending each one to the playback as it is read.
+struct Scan {
+    sender: Sender<ScanMessage>,
+    frame_count: usize,
+    session_count: usize,
+    last_timestamp: Duration,
+    previous: Option<EngineSnapshot>,
+    gc_log: GcLog,
+    slot_history: SlotHistory
+}
+
+impl Scan {
+    fn run(&mut self, paths: Vec<PathBuf>, profile: &GameProfile) -> io::Result<()> {
+        let first_modified = fs::metadata(&paths[0])?.modified().ok();
+
+        for (file, path) in paths.into_iter().enumerate() {
+            if is_session_file(&path) {
+                // A session is laid out after whatever came before it, using its own frame times.
+                let start = self.last_timestamp;
+                let session = self.session_count;
+                let mut reader = SessionReader::open(&path)?;
+                self.send(ScanMessage::SessionStarted(path))?;
+                self.session_count += 1;
+
+                for frame in 0.. {
+                    if frame % SESSION_CHECKPOINT_INTERVAL == 0 {
+                        self.send(ScanMessage::Checkpoint(reader.checkpoint()?))?;
+                    }
+
+                    let (timestamp, snapshot) = match reader.next_frame()? {
+                        Some(frame) => frame,
+                        None => break
+                    };
+
+                    self.push(start + timestamp, FrameSource::Session { session: session, frame: frame }, Some(snapshot))?;
+                }
+            } else {
+                // Fall back to one second per dump when the file times are not usable.
+                let timestamp = match (first_modified, fs::metadata(&path)?.modified().ok()) {
+                    (Some(first), Some(modified)) => modified.duration_since(first).unwrap_or_default(),
+                    _ => Duration::from_secs(self.frame_count as u64)
+                };
+
+                let snapshot = read_frame(&path, profile)?;
+                self.push(timestamp, FrameSource::File(path), snapshot)?;
+            }
+
+            self.send(ScanMessage::Progress(file + 1))?;
+        }
+
+        Ok(())
+    }
+
+    // Fails once the playback was closed, which ends the scan.
+    fn send(&self, message: ScanMessage) -> io::Result<()> {
+        self.sender.send(message).map_err(|_| io::Error::new(io::ErrorKind::Interrupted, "Playback was closed."))
+    }
+
+    // Markers are sent after their frame so the playback never holds one past its last frame.
+    fn push(&mut self, timestamp: Duration, source: FrameSource, snapshot: Option<EngineSnapshot>) -> io::Result<()> {
+        let frame = self.frame_count;
+
+        // Sessions and dumps in one folder are not on the same clock, keep the timeline in order anyway.
+        let timestamp = timestamp.max(self.last_timestamp);
+        self.send(ScanMessage::Frame(PlaybackFrame { timestamp: timestamp, source: source }))?;
+
+        if let Some(current) = &snapshot {
+            self.slot_history.record(current, timestamp);
+        }
+
+        if let (Some(previous), Some(current)) = (&self.previous, &snapshot) {
+            let events = diff_snapshots(previous, current, DEFAULT_MOVE_THRESHOLD);
+
+            if let Some(gc_event) = self.gc_log.process(previous, current, &events, timestamp) {
+                let label = format!("Garbage collected {} objects", gc_event.objects.len());
+                self.send(ScanMessage::Marker(PlaybackMarker { frame: frame, label: label }))?;
+            }
+
+            for event in &events {
+                let label = match event {
+                    SnapshotEvent::PlayerUnitChanged { player_index, previous, current } => {
+                        let unit = |datum: &Option<DatumHandle>| datum.as_ref().map_or("none".to_string(), |datum| datum.get_handle().to_string());
+                        format!("Player {} unit {} -> {}", player_index, unit(previous), unit(current))
+                    }
+                    SnapshotEvent::DeadPlayerDatumChanged { local_player_index, previous, current } => {
+                        format!("Player {} next datum {} -> {}", local_player_index, previous.get_handle(), current.get_handle())
+                    }
+                    _ => continue
+                };
+
+                self.send(ScanMessage::Marker(PlaybackMarker { frame: frame, label: label }))?;
+            }
+        }
+
+        self.frame_count += 1;
+        self.last_timestamp = timestamp;
+        self.previous = snapshot;
+        Ok(())
+    }
+}
+
+// Reads every frame once in order without keeping any of them, for headless mode.
+pub struct FrameReader {
+    paths: std::vec::IntoIter<PathBuf>,
+    session: Option<SessionReader>,
+    profile: GameProfile
+}
+
+impl FrameReader {
+    pub fn open(path: &Path, profile: &GameProfile) -> io::Result<FrameReader> {
+        Ok(FrameReader {
+            paths: playback_paths(path)?.into_iter(),
+            session: None,
+            profile: profile.clone()
+        })
+    }
+
+    // None once every frame was read, Some(None) for a RAM dump without a valid snapshot.
+    pub fn next_frame(&mut self) -> io::Result<Option<Option<EngineSnapshot>>> {
+        loop {
+            if let Some(session) = self.session.as_mut() {
+                match session.next_frame()? {
+                    Some((_, snapshot)) => return Ok(Some(Some(snapshot))),
+                    None => self.session = None
+                }
+            }
+
+            let path = match self.paths.next() {
+                Some(path) => path,
+                None => return Ok(None)
+            };
+
+            if is_session_file(&path) {
+                self.session = Some(SessionReader::open(&path)?);
+            } else {
+                return read_frame(&path, &self.profile).map(Some);
+            }
+        }
+    }
+}
+
+// The file itself, or the snapshots, sessions and RAM dumps in a folder ordered by file name.
+fn playback_paths(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+        if entry.file_type()?.is_file() && (is_snapshot_file(&entry_path) || is_session_file(&entry_path) || entry.metadata()?.len() as usize >= XBOX_RAM_SIZE) {
+            paths.push(entry_path);
+        }
+    }
+    paths.sort();
+
+    if paths.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "No RAM dumps, snapshots or sessions found in folder."));
+    }
+
+    Ok(paths)
+}
+
+fn is_snapshot_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == SNAPSHOT_EXTENSION)
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == SESSION_EXTENSION)
+}
+
+fn read_frame(path: &Path, profile: &GameProfile) -> io::Result<Option<EngineSnapshot>> {
+    if is_snapshot_file(path) {
+        return load_snapshot(path)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    }
+
+    let bytes = fs::read(path)?;
+    if bytes.len() < XBOX_RAM_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "File is smaller than guest RAM."));
+    }
+
+    Ok(build_snapshot(&bytes, profile))
+}
@@ -0,0 +1,502 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant}
+};
+
+use crate::engine::{
+    struct_bytes, struct_from_bytes, EngineSnapshot, GameObject, ObjectPoolEntry, ObjectPoolHeader, PlayerPoolEntry,
+    PlayerPoolHeader, PlayersGlobals, TagEntry
+};
+
+// Session files start with this magic followed by the format version.
+const SESSION_MAGIC: &[u8; 4] = b"OVSN";
+const SESSION_VERSION: u32 = 3;
+
+// Stored in place of the game tick when it is not known.
+const UNKNOWN_TICK: u32 = u32::MAX;
+
+pub const SESSION_EXTENSION: &str = "ovsession";
+
+// Tag paths are far shorter, a longer length means the file is corrupt.
+const MAX_TAG_PATH_LENGTH: usize = 256;
+
+// Layout of a session file, all integers little endian:
+//
+//   magic, version, object slot count (u16), player slot count (u16)
+//   frame*:
+//     timestamp in milliseconds (u64)
+//     game tick (u32, u32::MAX when unknown), since version 2
+//     ObjectPoolHeader, PlayerPoolHeader, PlayersGlobals
+//     tags reset (u8), since version 3
+//     new tag count (u32), [tag index (u32), TagEntry, path length (u32), path]*
+//     changed object count (u16), [slot (u16), present (u8), [ObjectPoolEntry, GameObject]]*
+//     changed player count (u16), [slot (u16), present (u8), [PlayerPoolEntry]]*
+//
+// Only slots that differ from the previous frame are written, tags are written the first time they are seen.
+// Tag indices are reused by every map, so once a known tag changes or goes away all tags are written again after a reset.
+pub struct SessionRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started: Instant,
+    frame_count: usize,
+    objects: Vec<Option<Vec<u8>>>,
+    players: Vec<Option<Vec<u8>>>,
+    // Entry bytes and path of every tag written so far.
+    known_tags: HashMap<u32, (Vec<u8>, String)>
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> io::Result<SessionRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(SESSION_MAGIC)?;
+        writer.write_all(&SESSION_VERSION.to_le_bytes())?;
+
+        Ok(SessionRecorder {
+            path: path.to_path_buf(),
+            writer: writer,
+            started: Instant::now(),
+            frame_count: 0,
+            objects: Vec::new(),
+            players: Vec::new(),
+            known_tags: HashMap::new()
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn record(&mut self, snapshot: &EngineSnapshot) -> io::Result<()> {
+        if self.frame_count == 0 {
+            self.objects = vec![None; snapshot.object_pool_entries.len()];
+            self.players = vec![None; snapshot.player_pool_entries.len()];
+            self.writer.write_all(&(self.objects.len() as u16).to_le_bytes())?;
+            self.writer.write_all(&(self.players.len() as u16).to_le_bytes())?;
+        }
+
+        let timestamp = self.started.elapsed().as_millis() as u64;
+        self.writer.write_all(&timestamp.to_le_bytes())?;
+        self.writer.write_all(&snapshot.game_tick.unwrap_or(UNKNOWN_TICK).to_le_bytes())?;
+        self.writer.write_all(struct_bytes(&snapshot.object_pool_header))?;
+        self.writer.write_all(struct_bytes(&snapshot.player_pool_header))?;
+        self.writer.write_all(struct_bytes(&snapshot.player_globals))?;
+
+        // Tags
+        let tags: HashMap<u32, (Vec<u8>, String)> = snapshot.tag_entries.iter()
+            .map(|(tag_index, tag_entry)| {
+                let path = snapshot.tags.get(tag_index).cloned().unwrap_or_default();
+                (*tag_index, (struct_bytes(tag_entry).to_vec(), path))
+            })
+            .collect();
+
+        let reset = self.known_tags.iter().any(|(tag_index, known)| tags.get(tag_index) != Some(known));
+        if reset {
+            self.known_tags.clear();
+        }
+
+        let new_tags: Vec<_> = tags.into_iter()
+            .filter(|(tag_index, _)| !self.known_tags.contains_key(tag_index))
+            .collect();
+
+        self.writer.write_all(&[reset as u8])?;
+        self.writer.write_all(&(new_tags.len() as u32).to_le_bytes())?;
+        for (tag_index, (tag_entry, path)) in new_tags {
+            self.writer.write_all(&tag_index.to_le_bytes())?;
+            self.writer.write_all(&tag_entry)?;
+            self.writer.write_all(&(path.len() as u32).to_le_bytes())?;
+            self.writer.write_all(path.as_bytes())?;
+            self.known_tags.insert(tag_index, (tag_entry, path));
+        }
+
+        // Objects
+        let mut changed_objects = Vec::new();
+        for (index, recorded) in self.objects.iter_mut().enumerate() {
+            let current = match (&snapshot.object_pool_entries[index], &snapshot.game_object_entries[index]) {
+                (Some(pool_entry), Some(game_object)) => Some([struct_bytes(pool_entry), struct_bytes(game_object)].concat()),
+                _ => None
+            };
+
+            if *recorded != current {
+                changed_objects.push((index as u16, current.clone()));
+                *recorded = current;
+            }
+        }
+        write_slots(&mut self.writer, &changed_objects)?;
+
+        // Players
+        let mut changed_players = Vec::new();
+        for (index, recorded) in self.players.iter_mut().enumerate() {
+            let current = snapshot.player_pool_entries[index].as_ref().map(player_entry_bytes);
+
+            if *recorded != current {
+                changed_players.push((index as u16, current.clone()));
+                *recorded = current;
+            }
+        }
+        write_slots(&mut self.writer, &changed_players)?;
+
+        self.writer.flush()?;
+        self.frame_count += 1;
+
+        Ok(())
+    }
+}
+
+// PlayerPoolEntry ends in two bytes of padding, so it is written field by field with the padding as zeroes.
+fn player_entry_bytes(entry: &PlayerPoolEntry) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(size_of::<PlayerPoolEntry>());
+    bytes.extend_from_slice(&entry.id.to_le_bytes());
+    bytes.extend_from_slice(&entry.local_player_index.to_le_bytes());
+    for character in &entry.player_name {
+        bytes.extend_from_slice(&character.to_le_bytes());
+    }
+    for value in &entry.unknown_1 {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&entry.slave_unit_index.get_handle().to_le_bytes());
+    bytes.extend_from_slice(&entry.last_slave_unit_index.get_handle().to_le_bytes());
+    bytes.extend_from_slice(&entry.unknown_2);
+    bytes.resize(size_of::<PlayerPoolEntry>(), 0);
+    bytes
+}
+
+fn write_slots(writer: &mut impl Write, slots: &[(u16, Option<Vec<u8>>)]) -> io::Result<()> {
+    writer.write_all(&(slots.len() as u16).to_le_bytes())?;
+    for (index, bytes) in slots {
+        writer.write_all(&index.to_le_bytes())?;
+        match bytes {
+            Some(bytes) => {
+                writer.write_all(&[1])?;
+                writer.write_all(bytes)?;
+            }
+            None => writer.write_all(&[0])?
+        }
+    }
+
+    Ok(())
+}
+
+// Where a reader stood between two frames, decoding can resume from here without reading the frames before it.
+#[derive(Clone)]
+pub struct SessionCheckpoint {
+    position: u64,
+    objects: Vec<Option<(Vec<u8>, Vec<u8>)>>,
+    players: Vec<Option<Vec<u8>>>,
+    tags: HashMap<u32, String>,
+    tag_entries: HashMap<u32, Vec<u8>>
+}
+
+pub struct SessionReader {
+    reader: BufReader<File>,
+    version: u32,
+    objects: Vec<Option<(Vec<u8>, Vec<u8>)>>,
+    players: Vec<Option<Vec<u8>>>,
+    tags: HashMap<u32, String>,
+    tag_entries: HashMap<u32, Vec<u8>>
+}
+
+impl SessionReader {
+    pub fn open(path: &Path) -> io::Result<SessionReader> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != SESSION_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a session file."));
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version == 0 || version > SESSION_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unsupported session version {}.", version)));
+        }
+
+        Ok(SessionReader {
+            reader: reader,
+            version: version,
+            objects: Vec::new(),
+            players: Vec::new(),
+            tags: HashMap::new(),
+            tag_entries: HashMap::new()
+        })
+    }
+
+    // Open a session at a checkpoint taken by an earlier reader of the same file.
+    pub fn resume(path: &Path, checkpoint: &SessionCheckpoint) -> io::Result<SessionReader> {
+        let mut reader = SessionReader::open(path)?;
+        reader.reader.seek(SeekFrom::Start(checkpoint.position))?;
+        reader.objects = checkpoint.objects.clone();
+        reader.players = checkpoint.players.clone();
+        reader.tags = checkpoint.tags.clone();
+        reader.tag_entries = checkpoint.tag_entries.clone();
+        Ok(reader)
+    }
+
+    // Taken before the next frame is read.
+    pub fn checkpoint(&mut self) -> io::Result<SessionCheckpoint> {
+        Ok(SessionCheckpoint {
+            position: self.reader.stream_position()?,
+            objects: self.objects.clone(),
+            players: self.players.clone(),
+            tags: self.tags.clone(),
+            tag_entries: self.tag_entries.clone()
+        })
+    }
+
+    // Returns None once the end of the session is reached.
+    pub fn next_frame(&mut self) -> io::Result<Option<(Duration, EngineSnapshot)>> {
+        if self.objects.is_empty() {
+            let mut slot_counts = [0u8; 4];
+            match self.reader.read_exact(&mut slot_counts) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?
+            }
+
+            self.objects = vec![None; u16::from_le_bytes([slot_counts[0], slot_counts[1]]) as usize];
+            self.players = vec![None; u16::from_le_bytes([slot_counts[2], slot_counts[3]]) as usize];
+        }
+
+        let mut timestamp = [0u8; 8];
+        match self.reader.read_exact(&mut timestamp) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?
+        }
+        let timestamp = Duration::from_millis(u64::from_le_bytes(timestamp));
+
+        let game_tick = match self.version {
+            1 => None,
+            _ => Some(read_u32(&mut self.reader)?).filter(|tick| *tick != UNKNOWN_TICK)
+        };
+
+        let object_pool_header: ObjectPoolHeader = read_struct(&mut self.reader)?;
+        let player_pool_header: PlayerPoolHeader = read_struct(&mut self.reader)?;
+        let player_globals: PlayersGlobals = read_struct(&mut self.reader)?;
+
+        // Tags
+        if self.version >= 3 && read_bytes(&mut self.reader, 1)?[0] != 0 {
+            self.tags.clear();
+            self.tag_entries.clear();
+        }
+
+        for _ in 0..read_u32(&mut self.reader)? {
+            let tag_index = read_u32(&mut self.reader)?;
+            let tag_entry = read_bytes(&mut self.reader, size_of::<TagEntry>())?;
+            let path_length = read_u32(&mut self.reader)? as usize;
+            if path_length > MAX_TAG_PATH_LENGTH {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Tag {} has a path of {} bytes.", tag_index, path_length)));
+            }
+            let path = read_bytes(&mut self.reader, path_length)?;
+
+            self.tag_entries.insert(tag_index, tag_entry);
+            self.tags.insert(tag_index, String::from_utf8_lossy(&path).to_string());
+        }
+
+        // Objects
+        for _ in 0..read_u16(&mut self.reader)? {
+            let index = read_u16(&mut self.reader)? as usize;
+            let present = read_bytes(&mut self.reader, 1)?[0] != 0;
+
+            let slot = if present {
+                Some((
+                    read_bytes(&mut self.reader, size_of::<ObjectPoolEntry>())?,
+                    read_bytes(&mut self.reader, size_of::<GameObject>())?
+                ))
+            } else {
+                None
+            };
+
+            *self.objects.get_mut(index).ok_or(invalid_slot(index))? = slot;
+        }
+
+        // Players
+        for _ in 0..read_u16(&mut self.reader)? {
+            let index = read_u16(&mut self.reader)? as usize;
+            let present = read_bytes(&mut self.reader, 1)?[0] != 0;
+
+            let slot = if present {
+                Some(read_bytes(&mut self.reader, size_of::<PlayerPoolEntry>())?)
+            } else {
+                None
+            };
+
+            *self.players.get_mut(index).ok_or(invalid_slot(index))? = slot;
+        }
+
+        let snapshot = EngineSnapshot {
+            object_pool_header: object_pool_header,
+            object_pool_entries: self.objects.iter().map(|slot| slot.as_ref().map(|(entry, _)| struct_from_bytes(entry))).collect(),
+            game_object_entries: self.objects.iter().map(|slot| slot.as_ref().map(|(_, object)| struct_from_bytes(object))).collect(),
+            player_pool_header: player_pool_header,
+            player_globals: player_globals,
+            player_pool_entries: self.players.iter().map(|slot| slot.as_ref().map(|entry| struct_from_bytes(entry))).collect(),
+            tags: self.tags.clone(),
+            tag_entries: self.tag_entries.iter().map(|(tag_index, entry)| (*tag_index, struct_from_bytes(entry))).collect(),
+            game_tick: game_tick
+        };
+
+        Ok(Some((timestamp, snapshot)))
+    }
+}
+
+fn invalid_slot(index: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Slot {} is out of range.", index))
+}
+
+fn read_bytes(reader: &mut impl Read, length: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_struct<T>(reader: &mut impl Read) -> io::Result<T> {
+    Ok(struct_from_bytes(&read_bytes(reader, size_of::<T>())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::DatumHandle;
+
+    // Zeroed bytes every engine struct can be built from.
+    const ZEROES: [u8; 0x400] = [0; 0x400];
+
+    fn snapshot_with_tags(tags: &[(u32, &str, u8)]) -> EngineSnapshot {
+        let mut snapshot = EngineSnapshot {
+            object_pool_header: struct_from_bytes(&ZEROES),
+            object_pool_entries: (0..8).map(|_| None).collect(),
+            game_object_entries: (0..8).map(|_| None).collect(),
+            player_pool_header: struct_from_bytes(&ZEROES),
+            player_globals: struct_from_bytes(&ZEROES),
+            player_pool_entries: (0..2).map(|_| None).collect(),
+            tags: Default::default(),
+            tag_entries: Default::default(),
+            game_tick: Some(100)
+        };
+
+        for (tag_index, path, class) in tags {
+            let mut tag_entry: TagEntry = struct_from_bytes(&[*class; 32]);
+            tag_entry.tag_index = *tag_index;
+            snapshot.tag_entries.insert(*tag_index, tag_entry);
+            snapshot.tags.insert(*tag_index, path.to_string());
+        }
+
+        snapshot
+    }
+
+    fn place_object(snapshot: &mut EngineSnapshot, index: usize, id: u16, tag_index: u32, position: [f32; 3]) {
+        let mut pool_entry: ObjectPoolEntry = struct_from_bytes(&ZEROES);
+        pool_entry.id = id;
+        let mut game_object: GameObject = struct_from_bytes(&ZEROES);
+        game_object.tag_index = tag_index;
+        game_object.position = position;
+
+        snapshot.object_pool_entries[index] = Some(pool_entry);
+        snapshot.game_object_entries[index] = Some(game_object);
+    }
+
+    fn temp_session(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("objectviewer-{}-{}.{}", name, std::process::id(), SESSION_EXTENSION))
+    }
+
+    fn read_all(path: &Path) -> Vec<EngineSnapshot> {
+        let mut reader = SessionReader::open(path).unwrap();
+        let mut snapshots = Vec::new();
+        while let Some((_, snapshot)) = reader.next_frame().unwrap() {
+            snapshots.push(snapshot);
+        }
+        snapshots
+    }
+
+    #[test]
+    fn recorded_frames_read_back() {
+        let path = temp_session("round-trip");
+
+        let mut first = snapshot_with_tags(&[(0xE1740000, "characters\\cyborg\\cyborg", 0x61)]);
+        place_object(&mut first, 0, 0xE174, 0xE1740000, [1.0, 2.0, 3.0]);
+        let mut player: PlayerPoolEntry = struct_from_bytes(&ZEROES);
+        player.id = 0xEC70;
+        player.slave_unit_index = DatumHandle::new_from_index_id(0, 0xE174);
+        first.player_pool_entries[1] = Some(player);
+
+        let mut second = snapshot_with_tags(&[(0xE1740000, "characters\\cyborg\\cyborg", 0x61)]);
+        place_object(&mut second, 3, 0xE175, 0xE1740000, [4.0, 5.0, 6.0]);
+        second.game_tick = None;
+
+        let mut recorder = SessionRecorder::create(&path).unwrap();
+        recorder.record(&first).unwrap();
+        recorder.record(&second).unwrap();
+        drop(recorder);
+
+        let snapshots = read_all(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].game_tick, Some(100));
+        assert_eq!(snapshots[1].game_tick, None);
+
+        let object = snapshots[0].game_object_entries[0].as_ref().unwrap();
+        assert_eq!(object.position, [1.0, 2.0, 3.0]);
+        assert_eq!(snapshots[0].object_pool_entries[0].as_ref().unwrap().id, 0xE174);
+        assert_eq!(snapshots[0].player_pool_entries[1].as_ref().unwrap().slave_unit_index.get_handle(), 0xE1740000);
+
+        assert!(snapshots[1].object_pool_entries[0].is_none());
+        assert!(snapshots[1].player_pool_entries[1].is_none());
+        assert_eq!(snapshots[1].game_object_entries[3].as_ref().unwrap().position, [4.0, 5.0, 6.0]);
+        assert_eq!(snapshots[1].tags.get(&0xE1740000).map(|path| path.as_str()), Some("characters\\cyborg\\cyborg"));
+        assert_eq!(struct_bytes(&snapshots[1].tag_entries[&0xE1740000]), struct_bytes(&second.tag_entries[&0xE1740000]));
+    }
+
+    #[test]
+    fn map_change_replaces_reused_tag_indices() {
+        let path = temp_session("map-change");
+
+        let mut recorder = SessionRecorder::create(&path).unwrap();
+        recorder.record(&snapshot_with_tags(&[(1, "first map", 0x61), (2, "gone after the map change", 0x61)])).unwrap();
+        recorder.record(&snapshot_with_tags(&[(1, "second map", 0x62)])).unwrap();
+        drop(recorder);
+
+        let snapshots = read_all(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(snapshots[0].tags.len(), 2);
+        assert_eq!(snapshots[1].tags.len(), 1);
+        assert_eq!(snapshots[1].tags[&1], "second map");
+        assert_eq!(snapshots[1].tag_entries[&1].tag_class, [0x62; 4]);
+    }
+
+    #[test]
+    fn oversized_tag_path_is_rejected() {
+        let path = temp_session("oversized-path");
+
+        let mut recorder = SessionRecorder::create(&path).unwrap();
+        recorder.record(&snapshot_with_tags(&[(1, &"a".repeat(MAX_TAG_PATH_LENGTH + 1), 0x61)])).unwrap();
+        drop(recorder);
+
+        let result = SessionReader::open(&path).unwrap().next_frame();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(result.err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+}
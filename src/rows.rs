@@ -0,0 +1,57 @@
+use crate::engine::{DatumHandle, EngineSnapshot};
+
+// One occupied row of the Objects table, shared by the UI and the headless exporter.
+pub struct ObjectRow {
+    pub datum: DatumHandle,
+    pub index: usize,
+    pub id: u16,
+    // Local player controlling this unit.
+    pub player: Option<usize>,
+    // Local player that will respawn into this slot.
+    pub dead_player: Option<usize>,
+    pub position: [f32; 3],
+    pub tag_name: String,
+    pub tag_class: String,
+    pub tag_class_secondary: String,
+    pub tag_class_tertiary: String
+}
+
+// Tag classes are stored as reversed fourccs.
+pub fn tag_class_string(tag_class: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag_class).chars().rev().collect::<String>()
+}
+
+pub fn object_row(snapshot: &EngineSnapshot, index: usize) -> Option<ObjectRow> {
+    let object_pool_entry = snapshot.object_pool_entries.get(index)?.as_ref()?;
+    let game_object_entry = snapshot.game_object_entries.get(index)?.as_ref()?;
+
+    let datum = DatumHandle::new_from_index_id(index as u16, object_pool_entry.id);
+    let player = snapshot.find_local_player_index_from_unit_index(index as u16);
+    let dead_player = match player {
+        Some(_) => None,
+        None => snapshot.find_next_object_datum_player(datum.clone())
+    };
+
+    let tag_entry = snapshot.tag_entries.get(&game_object_entry.tag_index);
+
+    Some(ObjectRow {
+        datum: datum,
+        index: index,
+        id: object_pool_entry.id,
+        player: player,
+        dead_player: dead_player,
+        position: game_object_entry.position,
+        tag_name: snapshot.tags.get(&game_object_entry.tag_index).cloned().unwrap_or("UNKNOWN".to_string()),
+        tag_class: tag_entry.map(|entry| tag_class_string(&entry.tag_class)).unwrap_or_default(),
+        tag_class_secondary: tag_entry.map(|entry| tag_class_string(&entry.tag_class_secondary)).unwrap_or_default(),
+        tag_class_tertiary: tag_entry.map(|entry| tag_class_string(&entry.tag_class_tertiary)).unwrap_or_default()
+    })
+}
+
+// Occupied rows in the same descending index order as the Objects table.
+pub fn object_rows(snapshot: &EngineSnapshot) -> Vec<ObjectRow> {
+    (0..snapshot.object_pool_entries.len())
+        .rev()
+        .filter_map(|index| object_row(snapshot, index))
+        .collect()
+}
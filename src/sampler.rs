@@ -0,0 +1,316 @@
+use std::{
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TryIter},
+    thread,
+    time::{Duration, Instant}
+};
+
+use crate::{
+    engine::{find_game_time_globals, read_game_tick, read_snapshot, EngineSnapshot, GameProfile, TagCache, GAME_GLOBALS_SEARCH_END},
+    memory::{DirectMemory, GuestMemory, ProcessMemory}
+};
+
+pub const DEFAULT_SAMPLE_RATE: f32 = 60.0;
+
+// Samples the UI has not picked up yet, the sampler waits once this many are queued.
+const SAMPLE_QUEUE_LENGTH: usize = 256;
+
+// How often the game time is polled while waiting for the next tick.
+const TICK_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+// Sample anyway when the game has not ticked for this long, e.g. while it is paused.
+const TICK_WAIT_LIMIT: Duration = Duration::from_secs(1);
+
+// Times a snapshot is read again when the game ticked while it was being read.
+const TORN_READ_RETRIES: usize = 3;
+
+// Time between the two copies of guest RAM compared to locate the game time globals.
+const CALIBRATION_WINDOW: Duration = Duration::from_millis(500);
+const CALIBRATION_RETRY: Duration = Duration::from_secs(5);
+const CALIBRATION_ATTEMPTS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct SampleSettings {
+    pub interval: Duration,
+    // Take one snapshot per game tick instead of one per interval, once the game time is known.
+    pub tick_sync: bool,
+    // Suspend the emulator while a snapshot is read so every part of it comes from the same moment.
+    pub suspend: bool
+}
+
+pub struct Sample {
+    pub taken_at: Instant,
+    pub snapshot: Option<EngineSnapshot>,
+    // Bytes copied out of the emulator for this sample.
+    pub bytes_read: usize,
+    // The game ticked while the snapshot was read on every attempt.
+    pub torn: bool,
+    // How long the emulator was suspended for this sample.
+    pub pause: Option<Duration>,
+    // The sampler could not open the emulator, it stops after sending this.
+    pub error: Option<String>,
+    // The game time globals could not be found, snapshots carry no tick until the guest RAM address changes.
+    pub calibration_error: Option<String>
+}
+
+enum SamplerCommand {
+    SetVirtualAddress(usize),
+    SetSettings(SampleSettings)
+}
+
+// Reads guest RAM on its own thread so sampling does not depend on the display refresh rate.
+// The thread stops once the sampler is dropped.
+pub struct Sampler {
+    commands: Sender<SamplerCommand>,
+    samples: Receiver<Sample>
+}
+
+impl Sampler {
+    pub fn spawn(virtual_address: usize, pid: u32, profile: &GameProfile, settings: SampleSettings) -> Sampler {
+        let (command_sender, command_receiver) = mpsc::channel();
+        let (sample_sender, sample_receiver) = mpsc::sync_channel(SAMPLE_QUEUE_LENGTH);
+        let profile = profile.clone();
+
+        thread::spawn(move || {
+            // Process handles can not be sent across threads, so the sampler opens its own.
+            let memory = match ProcessMemory::new(virtual_address, pid, true) {
+                Ok(memory) => memory,
+                Err(e) => {
+                    let _ = sample_sender.send(Sample {
+                        taken_at: Instant::now(),
+                        snapshot: None,
+                        bytes_read: 0,
+                        torn: false,
+                        pause: None,
+                        error: Some(e),
+                        calibration_error: None
+                    });
+                    return;
+                }
+            };
+
+            let sample_loop = SampleLoop {
+                memory: memory,
+                game_time_address: profile.game_time_globals_address,
+                profile: profile,
+                tag_cache: TagCache::new(),
+                settings: settings,
+                commands: command_receiver,
+                last_tick: None,
+                calibration: None,
+                last_calibration: None,
+                calibration_attempts: 0,
+                calibration_error: None
+            };
+
+            sample_loop.run(sample_sender);
+        });
+
+        Sampler {
+            commands: command_sender,
+            samples: sample_receiver
+        }
+    }
+
+    pub fn set_virtual_address(&self, virtual_address: usize) {
+        let _ = self.commands.send(SamplerCommand::SetVirtualAddress(virtual_address));
+    }
+
+    pub fn set_settings(&self, settings: SampleSettings) {
+        let _ = self.commands.send(SamplerCommand::SetSettings(settings));
+    }
+
+    // Samples taken since the last call, oldest first.
+    pub fn samples(&self) -> TryIter<'_, Sample> {
+        self.samples.try_iter()
+    }
+}
+
+pub fn sample_interval(rate: f32) -> Duration {
+    Duration::from_secs_f32(1.0 / rate.max(1.0))
+}
+
+struct SampleLoop {
+    memory: ProcessMemory,
+    profile: GameProfile,
+    tag_cache: TagCache,
+    settings: SampleSettings,
+    commands: Receiver<SamplerCommand>,
+    game_time_address: Option<usize>,
+    last_tick: Option<u32>,
+    // First copy of the searched range and when it was taken, while locating the game time globals.
+    calibration: Option<(Instant, Vec<u8>)>,
+    last_calibration: Option<Instant>,
+    calibration_attempts: usize,
+    calibration_error: Option<String>
+}
+
+impl SampleLoop {
+    fn run(mut self, samples: SyncSender<Sample>) {
+        loop {
+            let started = Instant::now();
+            let sample = self.sample();
+            let valid = sample.snapshot.is_some();
+
+            if samples.send(sample).is_err() {
+                return;
+            }
+
+            // Only a running game has a ticking clock to find.
+            if valid {
+                self.calibrate();
+            }
+
+            let running = if self.settings.tick_sync && self.game_time_address.is_some() && valid {
+                self.wait_for_tick()
+            } else {
+                self.wait(self.settings.interval.saturating_sub(started.elapsed()))
+            };
+
+            if !running {
+                return;
+            }
+        }
+    }
+
+    // Sleep while handling commands, false once the sampler was dropped.
+    fn wait(&mut self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+
+            match self.commands.recv_timeout(remaining) {
+                Ok(command) => self.handle(command),
+                Err(RecvTimeoutError::Timeout) => return true,
+                Err(RecvTimeoutError::Disconnected) => return false
+            }
+        }
+    }
+
+    fn handle(&mut self, command: SamplerCommand) {
+        match command {
+            SamplerCommand::SetVirtualAddress(virtual_address) => {
+                self.memory.set_virtual_address(virtual_address);
+                self.tag_cache = TagCache::new();
+                self.game_time_address = self.profile.game_time_globals_address;
+                self.last_tick = None;
+                self.calibration = None;
+                self.last_calibration = None;
+                self.calibration_attempts = 0;
+                self.calibration_error = None;
+            }
+            SamplerCommand::SetSettings(settings) => self.settings = settings
+        }
+    }
+
+    fn read_tick(&self) -> Option<u32> {
+        read_game_tick(&mut DirectMemory(&self.memory), self.game_time_address?)
+    }
+
+    // Poll the game time until it moves past the last sampled tick, false once the sampler was dropped.
+    fn wait_for_tick(&mut self) -> bool {
+        let started = Instant::now();
+
+        while started.elapsed() < TICK_WAIT_LIMIT {
+            match self.read_tick() {
+                Some(tick) if Some(tick) == self.last_tick => {}
+                _ => return true
+            }
+
+            if !self.wait(TICK_POLL_INTERVAL) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    // Read a snapshot, reading it again if the game ticked in the middle of it.
+    fn sample(&mut self) -> Sample {
+        let mut attempt = 0;
+
+        loop {
+            let suspended = self.settings.suspend.then(|| self.memory.suspend());
+            let tick_before = self.read_tick();
+            let taken_at = Instant::now();
+
+            self.memory.begin_sample();
+            let mut snapshot = read_snapshot(&mut self.memory, &self.profile, &mut self.tag_cache);
+            let tick_after = self.read_tick();
+            let pause = suspended.map(|suspended| suspended.resume());
+            let torn = tick_before != tick_after;
+
+            if !torn || attempt == TORN_READ_RETRIES {
+                if let Some(snapshot) = snapshot.as_mut() {
+                    snapshot.game_tick = tick_after;
+                }
+
+                self.last_tick = tick_after;
+
+                return Sample {
+                    taken_at: taken_at,
+                    snapshot: snapshot,
+                    bytes_read: self.memory.bytes_read(),
+                    torn: torn,
+                    pause: pause,
+                    error: None,
+                    calibration_error: self.calibration_error.clone()
+                };
+            }
+
+            attempt += 1;
+        }
+    }
+
+    // Locate the game time globals by comparing two copies of guest RAM taken a moment apart.
+    // Sampling carries on in between, so this never stalls the sampler for the whole window.
+    fn calibrate(&mut self) {
+        if self.game_time_address.is_some() || self.calibration_attempts >= CALIBRATION_ATTEMPTS {
+            return;
+        }
+
+        match self.calibration.take() {
+            Some((first_taken_at, first)) => {
+                if first_taken_at.elapsed() < CALIBRATION_WINDOW {
+                    self.calibration = Some((first_taken_at, first));
+                    return;
+                }
+
+                let elapsed = first_taken_at.elapsed();
+                let mut second = vec![0u8; GAME_GLOBALS_SEARCH_END];
+                if !DirectMemory(&self.memory).read_into(0, &mut second) {
+                    return;
+                }
+
+                self.calibration_attempts += 1;
+                let candidates = find_game_time_globals(&first, &second, elapsed);
+
+                match candidates.as_slice() {
+                    [address] => {
+                        println!("Found the game time globals at {:#X}.", address);
+                        self.game_time_address = Some(*address);
+                    }
+                    _ if self.calibration_attempts == CALIBRATION_ATTEMPTS => {
+                        self.calibration_error = Some(format!("Could not locate the game time globals ({} candidates)", candidates.len()));
+                    }
+                    _ => {}
+                }
+            }
+            None => {
+                if self.last_calibration.is_some_and(|last_calibration| last_calibration.elapsed() < CALIBRATION_RETRY) {
+                    return;
+                }
+
+                let taken_at = Instant::now();
+                let mut first = vec![0u8; GAME_GLOBALS_SEARCH_END];
+                if DirectMemory(&self.memory).read_into(0, &mut first) {
+                    self.last_calibration = Some(taken_at);
+                    self.calibration = Some((taken_at, first));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::engine::EngineSnapshot;
+
+// Bump whenever a serialized engine struct changes shape.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+pub const SNAPSHOT_EXTENSION: &str = "json";
+
+// Saved as SnapshotDocument<&EngineSnapshot> so the snapshot does not have to be cloned.
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotDocument<S = EngineSnapshot> {
+    pub version: u32,
+    pub snapshot: S
+}
+
+pub fn snapshot_to_json(snapshot: &EngineSnapshot) -> Result<String, String> {
+    let document = SnapshotDocument { version: SNAPSHOT_FORMAT_VERSION, snapshot: snapshot };
+    serde_json::to_string_pretty(&document).map_err(|e| e.to_string())
+}
+
+pub fn save_snapshot(path: &Path, snapshot: &EngineSnapshot) -> Result<(), String> {
+    let json = snapshot_to_json(snapshot)?;
+    fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+pub fn load_snapshot(path: &Path) -> Result<EngineSnapshot, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+
+    // Check the version first so an old file reports a version mismatch rather than a missing field.
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32
+    }
+
+    let version: Version = serde_json::from_str(&json).map_err(|e| format!("{} is not a snapshot: {}", path.display(), e))?;
+    if version.version != SNAPSHOT_FORMAT_VERSION {
+        return Err(format!("{} uses snapshot format {}, expected {}.", path.display(), version.version, SNAPSHOT_FORMAT_VERSION));
+    }
+
+    let document: SnapshotDocument = serde_json::from_str(&json).map_err(|e| format!("Could not parse {}: {}", path.display(), e))?;
+    Ok(document.snapshot)
+}
+
+// Tag classes are stored reversed in memory, they are serialized the way they are displayed, e.g. "bipd".
+// Anything that is not printable ASCII, such as the 0xFFFFFFFF of an unused class, is written as hex instead.
+pub mod fourcc {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = *value;
+        bytes.reverse();
+
+        if bytes.iter().all(|byte| (0x20..0x7F).contains(byte)) {
+            serializer.serialize_str(&String::from_utf8_lossy(&bytes))
+        } else {
+            serializer.serialize_str(&format!("0x{:08X}", u32::from_be_bytes(bytes)))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 4], D::Error> {
+        let value = String::deserialize(deserializer)?;
+
+        // A four character code can itself start with 0x, only the ten character form is hex.
+        if let Some(hex) = value.strip_prefix("0x").filter(|hex| hex.len() == 8) {
+            let code = u32::from_str_radix(hex, 16).map_err(serde::de::Error::custom)?;
+            return Ok(code.to_le_bytes());
+        }
+
+        let mut bytes: [u8; 4] = value.as_bytes()
+            .try_into()
+            .map_err(|_| serde::de::Error::custom(format!("Expected a four character code, got {:?}", value)))?;
+        bytes.reverse();
+        Ok(bytes)
+    }
+}
+
+// Raw byte blobs and 24-bit pointers are serialized as hex strings.
+pub mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(value: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = value.iter().map(|byte| format!("{:02x}", byte)).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error> {
+        let value = String::deserialize(deserializer)?;
+        if value.len() != N * 2 || !value.is_ascii() {
+            return Err(serde::de::Error::custom(format!("Expected {} hex encoded bytes", N)));
+        }
+
+        let mut bytes = [0u8; N];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&value[index * 2..index * 2 + 2], 16).map_err(serde::de::Error::custom)?;
+        }
+
+        Ok(bytes)
+    }
+}
+
+// Byte blobs of any length, e.g. the old and new bytes of a journaled write.
+pub mod hex_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let hex: String = value.iter().map(|byte| format!("{:02x}", byte)).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        if value.len() % 2 != 0 || !value.is_ascii() {
+            return Err(serde::de::Error::custom("Expected hex encoded bytes"));
+        }
+
+        (0..value.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&value[index..index + 2], 16).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{struct_bytes, struct_from_bytes, TagEntry};
+
+    #[test]
+    fn fourcc_round_trips_bytes_that_are_not_ascii() {
+        let tag_entry: TagEntry = struct_from_bytes(&[0xFF; 32]);
+
+        let json = serde_json::to_string(&tag_entry).unwrap();
+        assert!(json.contains("\"tag_class\":\"0xFFFFFFFF\""), "{}", json);
+
+        let loaded: TagEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(struct_bytes(&loaded), struct_bytes(&tag_entry));
+    }
+
+    #[test]
+    fn fourcc_keeps_printable_codes_readable() {
+        let mut tag_entry: TagEntry = struct_from_bytes(&[0xFF; 32]);
+        tag_entry.tag_class = *b"dpib";
+        tag_entry.tag_class_secondary = *b"  x0";
+
+        let json = serde_json::to_string(&tag_entry).unwrap();
+        assert!(json.contains("\"tag_class\":\"bipd\""), "{}", json);
+
+        let loaded: TagEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(struct_bytes(&loaded), struct_bytes(&tag_entry));
+    }
+}
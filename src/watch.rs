@@ -0,0 +1,242 @@
+use std::{collections::BTreeMap, path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{load_json, save_json},
+    engine::{DatumHandle, EngineSnapshot},
+    memory::{DirectMemory, GuestMemory, ProcessMemory}
+};
+
+pub const WATCHLISTS_FILE: &str = "watchlists.json";
+
+// Key of the watches added while the scenario tag could not be found.
+pub const UNKNOWN_MAP: &str = "Unknown Map";
+
+// Watches of every map, keyed by scenario path.
+pub type Watchlists = BTreeMap<String, Vec<Watch>>;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlayerField {
+    Player,
+    Unit,
+    LastUnit,
+    DeadUnit,
+    Position
+}
+
+pub const PLAYER_FIELDS: [PlayerField; 5] = [PlayerField::Player, PlayerField::Unit, PlayerField::LastUnit, PlayerField::DeadUnit, PlayerField::Position];
+
+impl PlayerField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerField::Player => "Player Datum",
+            PlayerField::Unit => "Unit",
+            PlayerField::LastUnit => "Last Unit",
+            PlayerField::DeadUnit => "Dead Unit",
+            PlayerField::Position => "Unit Position"
+        }
+    }
+}
+
+// How the bytes at a watched address are decoded, all little endian.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ValueKind {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+    Datum
+}
+
+pub const VALUE_KINDS: [ValueKind; 6] = [ValueKind::U8, ValueKind::U16, ValueKind::U32, ValueKind::I32, ValueKind::F32, ValueKind::Datum];
+
+impl ValueKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueKind::U8 => "u8",
+            ValueKind::U16 => "u16",
+            ValueKind::U32 => "u32",
+            ValueKind::I32 => "i32",
+            ValueKind::F32 => "f32",
+            ValueKind::Datum => "Datum"
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ValueKind::U8 => 1,
+            ValueKind::U16 => 2,
+            _ => 4
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        let word = |length: usize| {
+            let mut word = [0u8; 4];
+            word[..length].copy_from_slice(&bytes[..length]);
+            u32::from_le_bytes(word)
+        };
+
+        match self {
+            ValueKind::U8 => bytes[0].to_string(),
+            ValueKind::U16 => word(2).to_string(),
+            ValueKind::U32 => format!("{} ({:#X})", word(4), word(4)),
+            ValueKind::I32 => (word(4) as i32).to_string(),
+            ValueKind::F32 => format!("{:.4}", f32::from_bits(word(4))),
+            ValueKind::Datum => format_datum(&DatumHandle::new_from_index_id(word(4) as u16, (word(4) >> 16) as u16))
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum WatchTarget {
+    // One object, only while its slot still holds that datum.
+    Datum(DatumHandle),
+    // Whatever occupies a pool slot.
+    Slot(u16),
+    Player { local_player: usize, field: PlayerField },
+    // A raw physical guest address, only readable from a live process.
+    Address { address: usize, kind: ValueKind }
+}
+
+impl WatchTarget {
+    pub fn name(&self) -> String {
+        match self {
+            WatchTarget::Datum(datum) => format!("Datum {}", datum.get_handle()),
+            WatchTarget::Slot(index) => format!("Slot {}", index),
+            WatchTarget::Player { local_player, field } => format!("Player {} {}", local_player, field.name()),
+            WatchTarget::Address { address, kind } => format!("{:#X} {}", address, kind.name())
+        }
+    }
+
+    pub fn read(&self, snapshot: &EngineSnapshot, memory: Option<&ProcessMemory>) -> String {
+        match self {
+            WatchTarget::Datum(datum) => {
+                let index = datum.get_index() as usize;
+                match snapshot.object_pool_entries.get(index).and_then(|entry| entry.as_ref()) {
+                    Some(entry) if entry.id == datum.get_id() => describe_object(snapshot, index),
+                    Some(entry) => format!("Gone, slot {} holds ID {}", index, entry.id),
+                    None => format!("Gone, slot {} is free", index)
+                }
+            }
+            WatchTarget::Slot(index) => {
+                match snapshot.object_pool_entries.get(*index as usize).and_then(|entry| entry.as_ref()) {
+                    Some(entry) => format!("ID {} {}", entry.id, describe_object(snapshot, *index as usize)),
+                    None => "Free".to_string()
+                }
+            }
+            WatchTarget::Player { local_player, field } => read_player_field(snapshot, *local_player, *field),
+            WatchTarget::Address { address, kind } => {
+                let memory = match memory {
+                    Some(memory) => memory,
+                    None => return "Needs a live process".to_string()
+                };
+
+                let mut bytes = vec![0u8; kind.size()];
+                if DirectMemory(memory).read_into(*address, &mut bytes) {
+                    kind.decode(&bytes)
+                } else {
+                    "Unreadable".to_string()
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Watch {
+    pub label: String,
+    pub target: WatchTarget,
+    // Value shown last and when it last changed, only kept while the viewer runs.
+    #[serde(skip)]
+    pub value: Option<String>,
+    #[serde(skip)]
+    pub changed_at: Option<Instant>
+}
+
+impl Watch {
+    pub fn new(label: String, target: WatchTarget) -> Watch {
+        Watch {
+            label: label,
+            target: target,
+            value: None,
+            changed_at: None
+        }
+    }
+
+    // Read the value again, the first read does not count as a change.
+    pub fn update(&mut self, snapshot: &EngineSnapshot, memory: Option<&ProcessMemory>) {
+        let value = self.target.read(snapshot, memory);
+        if self.value.as_ref().is_some_and(|previous| *previous != value) {
+            self.changed_at = Some(Instant::now());
+        }
+        self.value = Some(value);
+    }
+}
+
+pub fn format_datum(datum: &DatumHandle) -> String {
+    if datum.is_invald() {
+        "None".to_string()
+    } else {
+        format!("{} (index {} ID {})", datum.get_handle(), datum.get_index(), datum.get_id())
+    }
+}
+
+fn describe_object(snapshot: &EngineSnapshot, index: usize) -> String {
+    match snapshot.game_object_entries.get(index).and_then(|entry| entry.as_ref()) {
+        Some(game_object) => {
+            let tag_path = snapshot.tags.get(&game_object.tag_index).map(|path| path.as_str()).unwrap_or("UNKNOWN");
+            let [x, y, z] = game_object.position;
+            format!("{} X: {:.4} Y: {:.4} Z: {:.4}", tag_path, x, y, z)
+        }
+        None => "No object".to_string()
+    }
+}
+
+fn read_player_field(snapshot: &EngineSnapshot, local_player: usize, field: PlayerField) -> String {
+    let globals = &snapshot.player_globals;
+    let player = match globals.local_players.get(local_player) {
+        Some(player) => player,
+        None => return format!("There is no local player {}", local_player)
+    };
+
+    let entry = snapshot.player_pool_entries.get(player.get_index() as usize).and_then(|entry| entry.as_ref());
+    let object = |datum: &DatumHandle| {
+        if datum.is_invald() {
+            "None".to_string()
+        } else {
+            format!("{} {}", format_datum(datum), describe_object(snapshot, datum.get_index() as usize))
+        }
+    };
+
+    match (field, entry) {
+        (PlayerField::Player, _) => format_datum(player),
+        (PlayerField::DeadUnit, _) => object(&globals.local_dead_players[local_player]),
+        (_, None) => "No player".to_string(),
+        (PlayerField::Unit, Some(entry)) => object(&entry.slave_unit_index),
+        (PlayerField::LastUnit, Some(entry)) => object(&entry.last_slave_unit_index),
+        (PlayerField::Position, Some(entry)) => {
+            let unit = &entry.slave_unit_index;
+            match snapshot.game_object_entries.get(unit.get_index() as usize).and_then(|entry| entry.as_ref()) {
+                Some(game_object) if !unit.is_invald() => {
+                    let [x, y, z] = game_object.position;
+                    format!("X: {:.4} Y: {:.4} Z: {:.4}", x, y, z)
+                }
+                _ => "No unit".to_string()
+            }
+        }
+    }
+}
+
+pub fn load_watchlists(path: &Path) -> Watchlists {
+    load_json(path).unwrap_or_else(|e| {
+        println!("{}", e);
+        Watchlists::new()
+    })
+}
+
+pub fn save_watchlists(path: &Path, watchlists: &Watchlists) -> Result<(), String> {
+    save_json(path, watchlists)
+}